@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+
+use crate::{forge, items, pricing, util};
+
+static ACCESSORIES: LazyLock<Value> =
+    LazyLock::new(|| util::parse_json_str(include_str!("../data/accessories.json")));
+
+const RECOMBOBULATOR: &str = "RECOMBOBULATOR_3000";
+
+fn get_magic_power(rarity: &str) -> f64 {
+    return ACCESSORIES["magic_power"][rarity].as_f64().unwrap_or(0.0);
+}
+
+fn get_family(id: &str) -> Option<(&'static Vec<Value>, usize)> {
+    for family in ACCESSORIES["families"].as_array().unwrap() {
+        let members = family.as_array().unwrap();
+        if let Some(position) = members.iter().position(|member| member.eq(id)) {
+            return Some((members, position));
+        }
+    }
+    return None;
+}
+
+// returns the MP of the best lower family member, or None if an equal or higher member is owned
+fn get_replaced(id: &str, owned: &HashMap<String, String>) -> Option<(f64, Option<String>)> {
+    let mut current = (0.0, None);
+    if let Some((members, position)) = get_family(id) {
+        for (other_position, member) in members.iter().enumerate() {
+            let member_id = member.as_str().unwrap();
+            if member_id.eq(id) {
+                continue;
+            }
+            if let Some(rarity) = owned.get(member_id) {
+                if other_position > position {
+                    return None;
+                }
+                let magic_power = get_magic_power(rarity);
+                if magic_power >= current.0 {
+                    current = (magic_power, Some(member_id.to_owned()));
+                }
+            }
+        }
+    }
+    return Some(current);
+}
+
+pub async fn get_upgrades_json(body: &Value) -> Option<BoxBody> {
+    let mut owned = HashMap::new();
+    for accessory in body["accessories"].as_array()? {
+        let id = accessory["id"].as_str()?.to_uppercase();
+        let rarity = accessory["rarity"].as_str()?.to_uppercase();
+        owned.insert(id, rarity);
+    }
+    let limit = body["limit"].as_u64().unwrap_or(50) as usize;
+    let tiers: HashMap<String, String> = items::get_data()
+        .await
+        .iter()
+        .filter(|(_id, data)| data["category"].as_str().is_some_and(|c| c.eq("ACCESSORY")))
+        .filter_map(|(id, data)| Some((id.to_owned(), data["tier"].as_str()?.to_owned())))
+        .collect();
+    let map = pricing::get().await;
+    let mut results = Vec::new();
+    for (id, tier) in tiers.iter() {
+        if owned.contains_key(id) {
+            continue;
+        }
+        let Some((current, replaces)) = get_replaced(id, &owned) else {
+            continue;
+        };
+        let magic_power = get_magic_power(tier) - current;
+        let cost = match (
+            pricing::get_buy_price(&map, id),
+            forge::get_recipe_cost(&map, id),
+        ) {
+            (Some(price), Some(craft)) => price.min(craft),
+            (price, craft) => price.or(craft).unwrap_or(0.0),
+        };
+        if magic_power > 0.0 && cost > 0.0 {
+            results.push(json!({
+                "type": "accessory",
+                "id": id,
+                "rarity": tier,
+                "replaces": replaces,
+                "magic_power": magic_power,
+                "cost": cost,
+                "coins_per_mp": cost / magic_power
+            }));
+        }
+    }
+    let recombobulator = pricing::get_buy_price(&map, RECOMBOBULATOR).unwrap_or(0.0);
+    for (id, rarity) in owned.iter() {
+        let is_recombobulated = tiers.get(id).is_none_or(|tier| !tier.eq(rarity));
+        if is_recombobulated || get_replaced(id, &owned).is_none() || recombobulator <= 0.0 {
+            continue; // superseded family members give no MP, so upgrading them is pointless
+        }
+        if let Some(next) = ACCESSORIES["rarity_upgrades"][rarity].as_str() {
+            let magic_power = get_magic_power(next) - get_magic_power(rarity);
+            results.push(json!({
+                "type": "recombobulate",
+                "id": id,
+                "rarity": next,
+                "replaces": Value::Null,
+                "magic_power": magic_power,
+                "cost": recombobulator,
+                "coins_per_mp": recombobulator / magic_power
+            }));
+        }
+    }
+    results.sort_by(|a, b| {
+        a["coins_per_mp"]
+            .as_f64()
+            .unwrap()
+            .total_cmp(&b["coins_per_mp"].as_f64().unwrap())
+    });
+    results.truncate(limit);
+    let json = json!({
+        "upgrades": results
+    });
+    return Some(BoxBody::new(json.to_string()));
+}
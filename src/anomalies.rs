@@ -0,0 +1,155 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::util;
+
+static HISTORY: LazyLock<Mutex<HashMap<String, VecDeque<Snapshot>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+static ANOMALIES: LazyLock<Mutex<Vec<Value>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+const HISTORY_SIZE: usize = 30; // roughly an hour of bazaar refreshes
+const MIN_HISTORY: usize = 5;
+const SPREAD_FACTOR: f64 = 3.0;
+const MIN_SPREAD: f64 = 0.1;
+const WALL_FACTOR: f64 = 10.0;
+const JUMP_FACTOR: f64 = 5.0;
+
+#[derive(Clone)]
+pub struct Snapshot {
+    pub buy: f64,
+    pub sell: f64,
+    pub sell_wall: f64, // size of the lowest sell offer, which instant buys are filled from
+    pub buy_wall: f64,  // size of the highest buy order, which instant sells are filled into
+}
+
+impl Snapshot {
+    pub fn from_product(data: &Value) -> Snapshot {
+        let get_top = |summary: &str, field: &str| -> f64 {
+            return data[summary][0][field].as_f64().unwrap_or(0.0);
+        };
+        return Snapshot {
+            buy: get_top("buy_summary", "pricePerUnit"),
+            sell: get_top("sell_summary", "pricePerUnit"),
+            sell_wall: get_top("buy_summary", "amount"),
+            buy_wall: get_top("sell_summary", "amount"),
+        };
+    }
+
+    fn get_spread(&self) -> f64 {
+        if self.buy <= 0.0 || self.sell <= 0.0 {
+            return 0.0;
+        }
+        return (self.buy - self.sell) / self.sell;
+    }
+}
+
+pub async fn get() -> MutexGuard<'static, HashMap<String, VecDeque<Snapshot>>> {
+    return HISTORY.lock().await;
+}
+
+fn get_history_median(history: &VecDeque<Snapshot>, value: impl Fn(&Snapshot) -> f64) -> f64 {
+    let mut values: Vec<f64> = history.iter().map(value).collect();
+    return util::get_median(&mut values);
+}
+
+fn is_jump(previous: f64, current: f64) -> bool {
+    if previous <= 0.0 || current <= 0.0 {
+        return false;
+    }
+    return current >= previous * JUMP_FACTOR || current * JUMP_FACTOR <= previous;
+}
+
+fn get_flags(history: &VecDeque<Snapshot>, snapshot: &Snapshot) -> Vec<&'static str> {
+    let mut flags = Vec::new();
+    if let Some(previous) = history.back()
+        && (is_jump(previous.buy, snapshot.buy) || is_jump(previous.sell, snapshot.sell))
+    {
+        flags.push("price_jump");
+    }
+    if history.len() < MIN_HISTORY {
+        return flags; // not enough refreshes yet to know what is normal for the product
+    }
+    let spread = snapshot.get_spread();
+    if spread > MIN_SPREAD
+        && spread > get_history_median(history, Snapshot::get_spread) * SPREAD_FACTOR
+    {
+        flags.push("spread");
+    }
+    let sell_wall = get_history_median(history, |entry| entry.sell_wall);
+    if sell_wall > 0.0 && snapshot.sell_wall > sell_wall * WALL_FACTOR {
+        flags.push("sell_wall");
+    }
+    let buy_wall = get_history_median(history, |entry| entry.buy_wall);
+    if buy_wall > 0.0 && snapshot.buy_wall > buy_wall * WALL_FACTOR {
+        flags.push("buy_wall");
+    }
+    return flags;
+}
+
+// compares every product against its own recent history, then records the new snapshots
+pub async fn detect_anomalies(
+    snapshots: &HashMap<String, Snapshot>,
+) -> HashMap<String, Vec<&'static str>> {
+    let mut history = get().await;
+    let mut flagged = HashMap::new();
+    let mut anomalies = Vec::new();
+    for (id, snapshot) in snapshots.iter() {
+        let entries = history.entry(id.to_owned()).or_default();
+        let flags = get_flags(entries, snapshot);
+        if !flags.is_empty() {
+            anomalies.push(json!({
+                "id": id,
+                "flags": flags,
+                "buy": snapshot.buy,
+                "sell": snapshot.sell,
+                "spread": snapshot.get_spread(),
+                "sell_wall": snapshot.sell_wall,
+                "buy_wall": snapshot.buy_wall,
+                "previous": entries.back().map(|previous| json!({
+                    "buy": previous.buy,
+                    "sell": previous.sell
+                })),
+                "median": {
+                    "spread": get_history_median(entries, Snapshot::get_spread),
+                    "sell_wall": get_history_median(entries, |entry| entry.sell_wall),
+                    "buy_wall": get_history_median(entries, |entry| entry.buy_wall)
+                }
+            }));
+            flagged.insert(id.to_owned(), flags);
+        }
+        entries.push_back(snapshot.to_owned());
+        if entries.len() > HISTORY_SIZE {
+            entries.pop_front();
+        }
+    }
+    history.retain(|id, _| snapshots.contains_key(id));
+    anomalies.sort_by(|a, b| a["id"].as_str().cmp(&b["id"].as_str()));
+    *ANOMALIES.lock().await = anomalies;
+    return flagged;
+}
+
+pub async fn get_anomalies_json(query: &HashMap<String, String>) -> BoxBody {
+    let flag = query.get("flag");
+    let anomalies: Vec<Value> = ANOMALIES
+        .lock()
+        .await
+        .iter()
+        .filter(|anomaly| {
+            flag.is_none_or(|flag| {
+                anomaly["flags"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .any(|entry| entry.as_str().unwrap().eq(flag))
+            })
+        })
+        .cloned()
+        .collect();
+    let json = json!({ "anomalies": anomalies });
+    return BoxBody::new(json.to_string());
+}
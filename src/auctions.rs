@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use actix_web::{body::BoxBody, http::StatusCode};
+use serde_json::{Value, json};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{limiter, pricing, util};
+
+static AUCTIONS: LazyLock<Mutex<Vec<Auction>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+static PLAYER_AUCTIONS: LazyLock<Mutex<HashMap<String, (u128, Value)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+const PAGE_SIZE: usize = 50;
+
+const PLAYER_CACHE_TTL: u128 = 60000;
+
+const UPSTREAM_KEY: &str = "hypixel-player-auctions";
+const UPSTREAM_TTL: u128 = 60000;
+const UPSTREAM_LIMIT: usize = 60;
+
+pub struct Auction {
+    pub uuid: String,
+    pub seller: String,
+    pub item_id: String,
+    pub item_name: String,
+    pub tier: String,
+    pub bin: bool,
+    pub price: f64,
+    pub end: u64,
+    pub reforge: String,
+    pub stars: i32,
+    pub enchantments: HashMap<String, i32>,
+}
+
+impl Auction {
+    pub fn to_json(&self) -> Value {
+        return json!({
+            "uuid": self.uuid,
+            "seller": self.seller,
+            "item_id": self.item_id,
+            "item_name": self.item_name,
+            "tier": self.tier,
+            "bin": self.bin,
+            "price": self.price,
+            "end": self.end,
+            "reforge": self.reforge,
+            "stars": self.stars,
+            "enchantments": self.enchantments
+        });
+    }
+}
+
+pub async fn get() -> MutexGuard<'static, Vec<Auction>> {
+    return AUCTIONS.lock().await;
+}
+
+pub async fn decode_auction(auction: &Value) -> Option<Auction> {
+    let bytes = auction["item_bytes"]
+        .as_str()
+        .or(auction["item_bytes"]["data"].as_str())?; // the player endpoint still wraps the bytes in an object
+    let nbt = util::parse_item_nbt(bytes).await?;
+    let extra = nbt.get_compound("tag")?.get_compound("ExtraAttributes")?;
+    let mut enchantments = HashMap::new();
+    if let Some(enchants) = extra.get_compound("enchantments") {
+        for (name, level) in enchants.child_tags.iter() {
+            enchantments.insert(name.to_owned(), level.extract_int().unwrap_or(0));
+        }
+    }
+    let bin = auction["bin"].as_bool().unwrap_or(false);
+    let starting_bid = auction["starting_bid"].as_f64().unwrap();
+    let highest_bid = auction["highest_bid_amount"].as_f64().unwrap_or(0.0);
+    return Some(Auction {
+        uuid: auction["uuid"].as_str().unwrap().to_owned(),
+        seller: auction["auctioneer"].as_str().unwrap_or("").to_owned(),
+        item_id: pricing::get_item_id(extra)?,
+        item_name: auction["item_name"].as_str().unwrap_or("").to_owned(),
+        tier: auction["tier"].as_str().unwrap_or("").to_owned(),
+        bin,
+        price: if bin {
+            starting_bid
+        } else {
+            starting_bid.max(highest_bid)
+        },
+        end: auction["end"].as_u64().unwrap_or(0),
+        reforge: extra
+            .get_string("modifier")
+            .map(|modifier| modifier.to_uppercase())
+            .unwrap_or_default(),
+        stars: extra
+            .get_int("upgrade_level")
+            .or(extra.get_int("dungeon_item_level"))
+            .unwrap_or(0),
+        enchantments,
+    });
+}
+
+pub async fn update_auctions(auctions: Vec<Auction>) {
+    let mut list = get().await;
+    *list = auctions;
+}
+
+fn matches_enchantments(auction: &Auction, filter: &str) -> bool {
+    for entry in filter.split(',').filter(|entry| !entry.is_empty()) {
+        let (name, level) = entry.split_once(':').unwrap_or((entry, "0"));
+        let min_level = level.parse().unwrap_or(0);
+        match auction.enchantments.get(&name.to_lowercase()) {
+            Some(current) if *current >= min_level => {}
+            _ => return false,
+        }
+    }
+    return true;
+}
+
+pub async fn search_auctions_json(query: &HashMap<String, String>) -> BoxBody {
+    let list = get().await;
+    let item_id = query.get("item_id").map(|id| id.to_uppercase());
+    let bin = query.get("bin").map(|bin| bin.eq("true"));
+    let min_price = query
+        .get("min_price")
+        .and_then(|price| price.parse::<f64>().ok());
+    let max_price = query
+        .get("max_price")
+        .and_then(|price| price.parse::<f64>().ok());
+    let tier = query.get("tier").map(|tier| tier.to_uppercase());
+    let reforge = query.get("reforge").map(|reforge| reforge.to_uppercase());
+    let stars = query
+        .get("stars")
+        .and_then(|stars| stars.parse::<i32>().ok());
+    let seller = query
+        .get("seller")
+        .map(|seller| seller.replace('-', "").to_lowercase());
+    let enchantments = query.get("enchantments");
+    let mut results: Vec<&Auction> = list
+        .iter()
+        .filter(|auction| {
+            return item_id.as_ref().is_none_or(|id| auction.item_id.eq(id))
+                && bin.is_none_or(|bin| auction.bin == bin)
+                && min_price.is_none_or(|price| auction.price >= price)
+                && max_price.is_none_or(|price| auction.price <= price)
+                && tier.as_ref().is_none_or(|tier| auction.tier.eq(tier))
+                && reforge
+                    .as_ref()
+                    .is_none_or(|reforge| auction.reforge.eq(reforge))
+                && stars.is_none_or(|stars| auction.stars == stars)
+                && seller
+                    .as_ref()
+                    .is_none_or(|seller| auction.seller.eq(seller))
+                && enchantments.is_none_or(|filter| matches_enchantments(auction, filter));
+        })
+        .collect();
+    match query.get("sort").map(|sort| sort.as_str()) {
+        Some("end") => results.sort_by_key(|auction| auction.end),
+        _ => results.sort_by(|a, b| a.price.total_cmp(&b.price)),
+    }
+    if query.get("order").is_some_and(|order| order.eq("desc")) {
+        results.reverse();
+    }
+    let page = query
+        .get("page")
+        .and_then(|page| page.parse::<usize>().ok())
+        .unwrap_or(0);
+    let total_pages = results.len().div_ceil(PAGE_SIZE);
+    let auctions: Vec<Value> = results
+        .iter()
+        .skip(page * PAGE_SIZE)
+        .take(PAGE_SIZE)
+        .map(|auction| auction.to_json())
+        .collect();
+    let json = json!({
+        "page": page,
+        "total_pages": total_pages,
+        "total_auctions": results.len(),
+        "auctions": auctions
+    });
+    return BoxBody::new(json.to_string());
+}
+
+pub fn is_valid_uuid(uuid: &str) -> bool {
+    return uuid.len() == 32 && uuid.chars().all(|char| char.is_ascii_hexdigit());
+}
+
+async fn fetch_player_auctions(uuid: &str) -> Option<Value> {
+    let url = format!("v2/skyblock/auction?player={}", uuid);
+    let req = util::fetch_json(url).await;
+    if req.is_err() {
+        println!(
+            "Panicked while fetching auctions of player {}:\n{}",
+            uuid,
+            req.unwrap_err()
+        );
+        return None;
+    }
+    let json = req.unwrap()?;
+    let mut entries = Vec::new();
+    for auction in json["auctions"].as_array()? {
+        if let Some(entry) = decode_auction(auction).await {
+            entries.push((auction, entry));
+        }
+    }
+    let map = pricing::get().await;
+    let mut list = Vec::new();
+    for (auction, entry) in entries {
+        let mut data = entry.to_json();
+        let bids: Vec<Value> = auction["bids"]
+            .as_array()
+            .unwrap_or(&Vec::new())
+            .iter()
+            .map(|bid| {
+                json!({
+                    "bidder": bid["bidder"],
+                    "amount": bid["amount"],
+                    "timestamp": bid["timestamp"]
+                })
+            })
+            .collect();
+        data["start"] = auction["start"].to_owned();
+        data["claimed"] = auction["claimed"].to_owned();
+        data["bids"] = json!(bids);
+        data["lowest_bin"] = pricing::get_item_pricing(&map, "auction", &entry.item_id);
+        list.push(data);
+    }
+    return Some(json!({
+        "player": uuid,
+        "auctions": list
+    }));
+}
+
+pub async fn get_player_auctions_json(uuid: &str) -> Result<BoxBody, StatusCode> {
+    let timestamp = util::get_timestamp();
+    {
+        let mut cache = PLAYER_AUCTIONS.lock().await;
+        cache.retain(|_key, value| timestamp.saturating_sub(value.0) < PLAYER_CACHE_TTL);
+        if let Some((_time, json)) = cache.get(uuid) {
+            return Ok(BoxBody::new(json.to_string()));
+        }
+    }
+    // every uncached player costs a call on the shared api key, no matter which client asked
+    if limiter::is_limited(&UPSTREAM_KEY.to_string(), UPSTREAM_TTL, UPSTREAM_LIMIT).await {
+        return Err(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    let json = fetch_player_auctions(uuid)
+        .await
+        .ok_or(StatusCode::BAD_GATEWAY)?;
+    let body = BoxBody::new(json.to_string());
+    PLAYER_AUCTIONS
+        .lock()
+        .await
+        .insert(uuid.to_owned(), (timestamp, json));
+    return Ok(body);
+}
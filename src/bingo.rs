@@ -0,0 +1,90 @@
+use std::sync::LazyLock;
+
+use serde_json::{Value, json};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{cache, util};
+
+static BINGO: LazyLock<Mutex<Value>> = LazyLock::new(|| Mutex::new(Value::Null));
+
+pub async fn get() -> MutexGuard<'static, Value> {
+    return BINGO.lock().await;
+}
+
+fn strip_formatting(text: &str) -> String {
+    let mut result = String::new();
+    let mut chars = text.chars();
+    while let Some(char) = chars.next() {
+        if char == '§' {
+            chars.next(); // skips the formatting code after the section sign
+        } else {
+            result.push(char);
+        }
+    }
+    return result;
+}
+
+// personal goals have a single required amount, while community goals are split into tiers
+fn get_goal(goal: &Value) -> Value {
+    let tiers: Option<Vec<u64>> = goal["tiers"]
+        .as_array()
+        .map(|tiers| tiers.iter().filter_map(|tier| tier.as_u64()).collect());
+    let progress = goal["progress"].as_u64();
+    let (completed_tiers, next_tier) = match (tiers.as_ref(), progress) {
+        (Some(tiers), Some(progress)) => (
+            Some(tiers.iter().filter(|tier| **tier <= progress).count()),
+            tiers.iter().find(|tier| **tier > progress).copied(),
+        ),
+        _ => (None, None),
+    };
+    let lore = goal["lore"].as_str().map(strip_formatting);
+    return json!({
+        "id": goal["id"],
+        "name": goal["name"].as_str().map(strip_formatting),
+        "lore": lore,
+        "type": if tiers.is_some() { "community" } else { "personal" },
+        "required_amount": goal["requiredAmount"].as_u64(),
+        "tiers": tiers,
+        "progress": progress,
+        "completed_tiers": completed_tiers,
+        "next_tier": next_tier
+    });
+}
+
+pub async fn get_bingo_json() -> Vec<u8> {
+    let bingo = get().await;
+    if bingo.is_null() {
+        return json!({ "goals": [] }).to_string().into_bytes();
+    }
+    return bingo.to_string().into_bytes();
+}
+
+pub async fn cache_bingo() {
+    cache::store("bingo", cache::JSON_MIME, get_bingo_json().await).await;
+}
+
+pub async fn refresh_bingo() {
+    let req = util::make_request("v2/resources/skyblock/bingo").await;
+    if req.is_err() {
+        println!(
+            "Panicked while refreshing bingo data:\n{}",
+            req.unwrap_err()
+        );
+    } else {
+        if let Some(json) = util::parse_json(req.unwrap()) {
+            let goals: Vec<Value> = json["goals"]
+                .as_array()
+                .map_or(Vec::new(), |goals| goals.iter().map(get_goal).collect());
+            *get().await = json!({
+                "id": json["id"],
+                "name": json["name"],
+                "modifier": json["modifier"],
+                "start": json["start"],
+                "end": json["end"],
+                "last_updated": json["lastUpdated"],
+                "goals": goals
+            });
+            cache_bingo().await;
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::Write;
+use std::sync::{Arc, LazyLock};
+
+use actix_web::{
+    HttpRequest,
+    body::BoxBody,
+    dev::Response,
+    http::{
+        StatusCode,
+        header::{CONTENT_ENCODING, CONTENT_TYPE, ETAG, HeaderValue, VARY},
+    },
+    web::Bytes,
+};
+use flate2::{Compression, write::GzEncoder};
+use tokio::{
+    sync::{Mutex, MutexGuard},
+    task,
+};
+
+pub const JSON_MIME: &str = "application/json";
+pub const MSGPACK_MIME: &str = "application/msgpack";
+
+static RESPONSES: LazyLock<Mutex<HashMap<String, Arc<CachedResponse>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub struct CachedResponse {
+    content_type: &'static str,
+    hash: String,
+    identity: Bytes,
+    gzip: Bytes,
+    brotli: Bytes,
+}
+
+pub async fn get() -> MutexGuard<'static, HashMap<String, Arc<CachedResponse>>> {
+    return RESPONSES.lock().await;
+}
+
+fn encode_gzip(bytes: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(bytes).unwrap();
+    return encoder.finish().unwrap();
+}
+
+fn encode_brotli(bytes: &[u8]) -> Vec<u8> {
+    let mut output = Vec::new();
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut output, 4096, 9, 22);
+        writer.write_all(bytes).unwrap();
+    } // the writer only flushes the last block once dropped
+    return output;
+}
+
+fn get_hash(bytes: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    return format!("{:016x}{:08x}", hasher.finish(), bytes.len());
+}
+
+// encodes every variant up front, so requests only have to clone an Arc instead of locking the data
+pub async fn store(name: &str, content_type: &'static str, bytes: Vec<u8>) {
+    // compressing large payloads takes a while, so it runs off the async worker threads
+    let response = task::spawn_blocking(move || CachedResponse {
+        content_type,
+        hash: get_hash(&bytes),
+        gzip: Bytes::from(encode_gzip(&bytes)),
+        brotli: Bytes::from(encode_brotli(&bytes)),
+        identity: Bytes::from(bytes),
+    })
+    .await
+    .unwrap();
+    get().await.insert(name.to_owned(), Arc::new(response));
+}
+
+// the highest q-value the header gives any of the names, or None when none of them are listed
+pub fn get_quality(header: &str, names: &[&str]) -> Option<f64> {
+    let mut quality: Option<f64> = None;
+    for entry in header.split(',') {
+        let mut parts = entry.trim().split(';');
+        let name = parts.next().unwrap_or("").trim().to_lowercase();
+        if !names.contains(&name.as_str()) {
+            continue;
+        }
+        let value = parts
+            .find_map(|param| {
+                let param = param.replace(' ', "").to_lowercase();
+                return param.strip_prefix("q=")?.parse::<f64>().ok();
+            })
+            .unwrap_or(1.0);
+        quality = Some(quality.map_or(value, |current| current.max(value)));
+    }
+    return quality;
+}
+
+// an explicit entry for the coding takes precedence over the wildcard, and q=0 means refused
+fn accepts_encoding(req: &HttpRequest, encoding: &str) -> bool {
+    if let Some(header) = req.headers().get("accept-encoding") {
+        let header = header.to_str().unwrap_or("");
+        let quality = get_quality(header, &[encoding]).or(get_quality(header, &["*"]));
+        return quality.is_some_and(|quality| quality > 0.0);
+    }
+    return false;
+}
+
+fn is_not_modified(req: &HttpRequest, etag: &str) -> bool {
+    if let Some(header) = req.headers().get("if-none-match") {
+        return header.to_str().unwrap_or("").split(',').any(|tag| {
+            let tag = tag.trim().trim_start_matches("W/");
+            return tag.eq("*") || tag.eq(etag);
+        });
+    }
+    return false;
+}
+
+pub async fn respond(req: &HttpRequest, name: &str) -> Response<BoxBody> {
+    let cached = get().await.get(name).cloned();
+    if cached.is_none() {
+        return Response::new(StatusCode::SERVICE_UNAVAILABLE);
+    }
+    let response = cached.unwrap();
+    let (body, encoding) = if accepts_encoding(req, "br") {
+        (response.brotli.clone(), Some("br"))
+    } else if accepts_encoding(req, "gzip") {
+        (response.gzip.clone(), Some("gzip"))
+    } else {
+        (response.identity.clone(), None)
+    };
+    // each content coding is a different representation, so it gets its own strong validator
+    let etag = match encoding {
+        Some(encoding) => format!("\"{}-{}\"", response.hash, encoding),
+        None => format!("\"{}\"", response.hash),
+    };
+    let mut res = if is_not_modified(req, &etag) {
+        Response::new(StatusCode::NOT_MODIFIED)
+    } else {
+        Response::new(StatusCode::OK).set_body(BoxBody::new(body))
+    };
+    let headers = res.headers_mut();
+    headers.append(ETAG, HeaderValue::from_str(&etag).unwrap());
+    headers.append(VARY, HeaderValue::from_static("Accept, Accept-Encoding"));
+    if res.status() == StatusCode::OK {
+        let headers = res.headers_mut();
+        headers.append(
+            CONTENT_TYPE,
+            HeaderValue::from_static(response.content_type),
+        );
+        if let Some(encoding) = encoding {
+            headers.append(CONTENT_ENCODING, HeaderValue::from_static(encoding));
+        }
+    }
+    return res;
+}
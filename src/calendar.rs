@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+
+use crate::util;
+
+pub const EPOCH: u64 = 1560275700000; // the start of Early Spring 1st, Year 1
+pub const HOUR_LENGTH: u64 = 50000;
+pub const DAY_LENGTH: u64 = HOUR_LENGTH * 24;
+pub const MONTH_DAYS: u64 = 31;
+pub const MONTH_LENGTH: u64 = DAY_LENGTH * MONTH_DAYS;
+pub const YEAR_DAYS: u64 = MONTH_DAYS * 12;
+pub const YEAR_LENGTH: u64 = MONTH_LENGTH * 12;
+
+pub const MONTHS: [&str; 12] = [
+    "Early Spring",
+    "Spring",
+    "Late Spring",
+    "Early Summer",
+    "Summer",
+    "Late Summer",
+    "Early Autumn",
+    "Autumn",
+    "Late Autumn",
+    "Early Winter",
+    "Winter",
+    "Late Winter",
+];
+
+// years, months and days are all counted from 1, matching how the game displays them
+pub struct SkyBlockDate {
+    pub year: u64,
+    pub month: u64,
+    pub day: u64,
+    pub hour: u64,
+    pub minute: u64,
+}
+
+impl SkyBlockDate {
+    pub fn from_timestamp(timestamp: u64) -> SkyBlockDate {
+        let elapsed = timestamp.saturating_sub(EPOCH);
+        let in_year = elapsed % YEAR_LENGTH;
+        let in_day = in_year % DAY_LENGTH;
+        return SkyBlockDate {
+            year: elapsed / YEAR_LENGTH + 1,
+            month: in_year / MONTH_LENGTH + 1,
+            day: in_year % MONTH_LENGTH / DAY_LENGTH + 1,
+            hour: in_day / HOUR_LENGTH,
+            minute: in_day % HOUR_LENGTH * 60 / HOUR_LENGTH,
+        };
+    }
+
+    pub fn get_day_of_year(&self) -> u64 {
+        return (self.month - 1) * MONTH_DAYS + self.day;
+    }
+
+    pub fn get_season(&self) -> &'static str {
+        return MONTHS[self.month as usize - 1]
+            .split(' ')
+            .next_back()
+            .unwrap();
+    }
+
+    pub fn to_json(&self) -> Value {
+        let day_start = get_day_start(self.year, self.get_day_of_year()).unwrap();
+        return json!({
+            "year": self.year,
+            "month": self.month,
+            "month_name": MONTHS[self.month as usize - 1],
+            "season": self.get_season(),
+            "day": self.day,
+            "day_of_year": self.get_day_of_year(),
+            "hour": self.hour,
+            "minute": self.minute,
+            "day_start": day_start,
+            "next_day": day_start.checked_add(DAY_LENGTH),
+            "next_month": get_date_start(self.year, self.month, 1)
+                .and_then(|month_start| month_start.checked_add(MONTH_LENGTH)),
+            "next_year": get_year_start(self.year + 1)
+        });
+    }
+}
+
+// returns None once the year is too far out to be represented as a timestamp
+pub fn get_year_start(year: u64) -> Option<u64> {
+    return year
+        .saturating_sub(1)
+        .checked_mul(YEAR_LENGTH)?
+        .checked_add(EPOCH);
+}
+
+// returns the real timestamp at which the given day of the SkyBlock year starts
+pub fn get_day_start(year: u64, day: u64) -> Option<u64> {
+    if year == 0 || day == 0 || day > YEAR_DAYS {
+        return None;
+    }
+    return get_year_start(year)?.checked_add((day - 1) * DAY_LENGTH);
+}
+
+pub fn get_date_start(year: u64, month: u64, day: u64) -> Option<u64> {
+    if month == 0 || month > 12 || day == 0 || day > MONTH_DAYS {
+        return None;
+    }
+    return get_day_start(year, (month - 1) * MONTH_DAYS + day);
+}
+
+pub fn get_date_json(query: &HashMap<String, String>) -> Option<BoxBody> {
+    let get_number = |name: &str| -> Option<u64> { query.get(name)?.parse::<u64>().ok() };
+    let timestamp = if let Some(year) = get_number("year") {
+        match (get_number("month"), get_number("day")) {
+            (Some(month), Some(day)) => get_date_start(year, month, day)?,
+            (None, Some(day)) => get_day_start(year, day)?, // day of the year
+            (None, None) => get_year_start(year.max(1))?,
+            _ => return None,
+        }
+    } else if query.contains_key("timestamp") {
+        get_number("timestamp")?
+    } else {
+        util::get_timestamp() as u64
+    };
+    if timestamp < EPOCH {
+        return None;
+    }
+    let mut json = SkyBlockDate::from_timestamp(timestamp).to_json();
+    json["timestamp"] = json!(timestamp);
+    return Some(BoxBody::new(json.to_string()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_date(date: &SkyBlockDate, year: u64, month: u64, day: u64) {
+        assert_eq!((date.year, date.month, date.day), (year, month, day));
+    }
+
+    #[test]
+    fn epoch_is_first_day() {
+        let date = SkyBlockDate::from_timestamp(EPOCH);
+        assert_date(&date, 1, 1, 1);
+        assert_eq!((date.hour, date.minute), (0, 0));
+        assert_eq!(MONTHS[date.month as usize - 1], "Early Spring");
+        assert_eq!(get_date_start(1, 1, 1), Some(EPOCH));
+    }
+
+    #[test]
+    fn date_start_round_trip() {
+        for year in [1, 2, 100, 520] {
+            for month in 1..=12 {
+                for day in [1, 15, MONTH_DAYS] {
+                    let start = get_date_start(year, month, day).unwrap();
+                    assert_date(&SkyBlockDate::from_timestamp(start), year, month, day);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn day_of_year_matches_date() {
+        let start = get_day_start(3, 100).unwrap();
+        let date = SkyBlockDate::from_timestamp(start);
+        assert_date(&date, 3, 4, 7);
+        assert_eq!(date.get_day_of_year(), 100);
+    }
+
+    #[test]
+    fn boundaries() {
+        let day_end = get_date_start(1, 1, 1).unwrap() + DAY_LENGTH - 1;
+        assert_date(&SkyBlockDate::from_timestamp(day_end), 1, 1, 1);
+        assert_eq!(SkyBlockDate::from_timestamp(day_end).hour, 23);
+        let month_end = get_date_start(1, 1, MONTH_DAYS).unwrap() + DAY_LENGTH - 1;
+        assert_date(&SkyBlockDate::from_timestamp(month_end), 1, 1, 31);
+        assert_date(&SkyBlockDate::from_timestamp(month_end + 1), 1, 2, 1);
+        let year_end = get_year_start(2).unwrap() - 1;
+        assert_date(&SkyBlockDate::from_timestamp(year_end), 1, 12, 31);
+        assert_date(&SkyBlockDate::from_timestamp(year_end + 1), 2, 1, 1);
+        assert_eq!(get_date_start(1, 1, MONTH_DAYS + 1), None);
+        assert_eq!(get_date_start(1, 13, 1), None);
+        assert_eq!(get_day_start(1, YEAR_DAYS + 1), None);
+        assert_eq!(get_day_start(0, 1), None);
+    }
+
+    #[test]
+    fn overflow_returns_none() {
+        assert_eq!(get_year_start(99999999999999), None);
+        assert_eq!(get_day_start(u64::MAX, 1), None);
+        assert_eq!(get_date_start(u64::MAX, 12, 31), None);
+        let date = SkyBlockDate::from_timestamp(u64::MAX);
+        assert!(date.to_json()["next_year"].is_null());
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use crab_nbt::NbtCompound;
+use serde_json::{Value, json};
+use tokio::sync::MutexGuard;
+
+use crate::{pricing, util};
+
+static CHESTS: LazyLock<Value> =
+    LazyLock::new(|| util::parse_json_str(include_str!("../data/chests.json")));
+
+pub fn get_book_id(extra: &NbtCompound) -> Option<String> {
+    let enchants = extra.get_compound("enchantments")?;
+    if enchants.child_tags.len() != 1 {
+        return None; // books with multiple enchantments have no bazaar product
+    }
+    let (name, level) = enchants.child_tags.first()?;
+    return Some(format!(
+        "ENCHANTMENT_{}_{}",
+        name.to_uppercase(),
+        level.extract_int()?
+    ));
+}
+
+pub async fn decode_item(bytes: &str) -> Option<(String, f64)> {
+    let nbt = util::parse_item_nbt(bytes).await?;
+    let extra = nbt.get_compound("tag")?.get_compound("ExtraAttributes")?;
+    let count = nbt.get_byte("Count").unwrap_or(1) as f64;
+    let id = pricing::get_item_id(extra)?;
+    if id.eq("ENCHANTED_BOOK") {
+        return Some((get_book_id(extra).unwrap_or(id), count));
+    }
+    return Some((id, count));
+}
+
+fn get_key_cost(map: &MutexGuard<'static, HashMap<String, Value>>, key: &Value) -> f64 {
+    let mut cost = key["coins"].as_f64().unwrap_or(0.0);
+    for (item_id, amount) in key["items"].as_object().unwrap() {
+        cost += pricing::get_buy_price(map, item_id).unwrap_or(0.0) * amount.as_f64().unwrap();
+    }
+    return cost;
+}
+
+pub async fn get_chest_value_json(body: &Value) -> Option<BoxBody> {
+    let instance = body["instance"].as_str()?.to_lowercase();
+    let floor = body["floor"].as_str()?.to_uppercase();
+    let chest = body["chest"].as_str()?.to_uppercase();
+    let table = &CHESTS[&instance][&floor];
+    if !table.is_object() {
+        return None;
+    }
+    // the cost shown in the chest GUI depends on the rolled loot, so it overrides the base cost
+    let chest_cost = body["cost"].as_f64().or(table["chests"][&chest].as_f64())?;
+    let mut items = Vec::new();
+    for item in body["items"].as_array().unwrap_or(&Vec::new()) {
+        if let Some(id) = item.as_str() {
+            items.push((id.to_uppercase(), 1.0));
+        } else {
+            let id = item["id"].as_str()?.to_uppercase();
+            items.push((id, item["amount"].as_f64().unwrap_or(1.0)));
+        }
+    }
+    for bytes in body["item_bytes"].as_array().unwrap_or(&Vec::new()) {
+        items.push(decode_item(bytes.as_str()?).await?);
+    }
+    let map = pricing::get().await;
+    let use_key = body["key"].as_bool().unwrap_or(false)
+        || table["key_required"]
+            .as_array()
+            .is_some_and(|chests| chests.contains(&json!(chest)));
+    let key_cost = if use_key {
+        get_key_cost(&map, &table["key"])
+    } else {
+        0.0
+    };
+    let drops = table["drops"].as_array().unwrap();
+    let mut value = 0.0;
+    let mut results = Vec::new();
+    for (id, amount) in items {
+        let price = pricing::get_sell_price(&map, &id);
+        value += price.unwrap_or(0.0) * amount;
+        results.push(json!({
+            "id": id,
+            "amount": amount,
+            "price": price,
+            "known_drop": drops.contains(&json!(id))
+        }));
+    }
+    let cost = chest_cost + key_cost;
+    let json = json!({
+        "instance": instance,
+        "floor": floor,
+        "chest": chest,
+        "items": results,
+        "chest_cost": chest_cost,
+        "key_cost": key_cost,
+        "cost": cost,
+        "value": value,
+        "profit": value - cost
+    });
+    return Some(BoxBody::new(json.to_string()));
+}
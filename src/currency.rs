@@ -0,0 +1,79 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+use tokio::sync::MutexGuard;
+
+use crate::{pricing, util};
+
+static SHOPS: LazyLock<Value> =
+    LazyLock::new(|| util::parse_json_str(include_str!("../data/currencies.json")));
+
+const MOTE_SALES_LIMIT: usize = 100;
+
+// ranks the cheapest ways to earn motes by buying items with coins and selling them to a Rift NPC
+fn get_mote_sales(map: &MutexGuard<'static, HashMap<String, Value>>) -> Vec<Value> {
+    let mut results = Vec::new();
+    if let Some(npc) = map.get("npc").and_then(|npc| npc.as_object()) {
+        for (id, prices) in npc.iter() {
+            let motes = prices["mote"].as_f64().filter(|motes| *motes > 0.0);
+            let cost = pricing::get_buy_price(map, id);
+            if let (Some(motes), Some(cost)) = (motes, cost) {
+                results.push(json!({
+                    "id": id,
+                    "cost": cost,
+                    "motes": motes,
+                    "coins_per_mote": cost / motes
+                }));
+            }
+        }
+    }
+    results.sort_by(|a, b| {
+        a["coins_per_mote"]
+            .as_f64()
+            .unwrap()
+            .total_cmp(&b["coins_per_mote"].as_f64().unwrap())
+    });
+    results.truncate(MOTE_SALES_LIMIT);
+    return results;
+}
+
+pub async fn get_currency_values_json(query: &HashMap<String, String>) -> Option<BoxBody> {
+    let map = pricing::get().await;
+    let filter = query
+        .get("currency")
+        .map(|currency| currency.to_lowercase());
+    let mut json = json!({});
+    for (currency, items) in SHOPS.as_object().unwrap() {
+        if filter.as_ref().is_some_and(|filter| !filter.eq(currency)) {
+            continue;
+        }
+        let mut results = Vec::new();
+        for item in items.as_array().unwrap() {
+            let id = item["id"].as_str().unwrap();
+            let cost = item["cost"].as_f64().unwrap();
+            let value = pricing::get_sell_price(&map, id);
+            results.push(json!({
+                "id": id,
+                "cost": cost,
+                "value": value,
+                "coins_per_unit": value.unwrap_or(0.0) / cost
+            }));
+        }
+        results.sort_by(|a, b| {
+            b["coins_per_unit"]
+                .as_f64()
+                .unwrap()
+                .total_cmp(&a["coins_per_unit"].as_f64().unwrap())
+        });
+        json[currency] = json!(results);
+    }
+    if filter.as_ref().is_none_or(|filter| filter.eq("mote_sales")) {
+        json["mote_sales"] = json!(get_mote_sales(&map));
+    }
+    if filter.is_some() && json.as_object().unwrap().is_empty() {
+        return None;
+    }
+    return Some(BoxBody::new(json.to_string()));
+}
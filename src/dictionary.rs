@@ -0,0 +1,48 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::json;
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::util;
+
+static DICTIONARY: LazyLock<Mutex<Dictionary>> = LazyLock::new(|| {
+    Mutex::new(Dictionary {
+        id: util::get_timestamp() as u64,
+        items: Vec::new(),
+        indexes: HashMap::new(),
+    })
+});
+
+// item IDs are only ever appended, so clients can keep using a cached copy until its size is exceeded
+pub struct Dictionary {
+    pub id: u64,
+    pub items: Vec<String>,
+    indexes: HashMap<String, usize>,
+}
+
+impl Dictionary {
+    pub fn get_index(&mut self, item_id: &str) -> usize {
+        if let Some(index) = self.indexes.get(item_id) {
+            return *index;
+        }
+        let index = self.items.len();
+        self.items.push(item_id.to_owned());
+        self.indexes.insert(item_id.to_owned(), index);
+        return index;
+    }
+}
+
+pub async fn get() -> MutexGuard<'static, Dictionary> {
+    return DICTIONARY.lock().await;
+}
+
+pub async fn get_dictionary_json() -> BoxBody {
+    let dictionary = get().await;
+    let json = json!({
+        "id": dictionary.id,
+        "items": dictionary.items
+    });
+    return BoxBody::new(json.to_string());
+}
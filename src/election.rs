@@ -1,27 +1,342 @@
 use std::{collections::HashSet, sync::LazyLock};
 
 use actix_web::body::BoxBody;
-use serde_json::json;
-use tokio::sync::{Mutex, MutexGuard};
+use serde_json::{Value, json};
+use tokio::sync::{
+    Mutex, MutexGuard,
+    broadcast::{self, Sender},
+};
 
-use crate::util;
+use crate::{cache, history, util};
 
 static PERKS: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
 
+static STATE: LazyLock<Mutex<ElectionState>> = LazyLock::new(|| {
+    Mutex::new(ElectionState {
+        mayor: None,
+        minister: None,
+        last_election: None,
+        current_election: None,
+        last_updated: 0,
+    })
+});
+
+static PERK_CATALOGUE: LazyLock<Value> =
+    LazyLock::new(|| util::parse_json_str(include_str!("../data/perks.json")));
+
+static ELECTION_EVENTS: LazyLock<Sender<Value>> = LazyLock::new(|| broadcast::channel(64).0);
+
+#[derive(Clone)]
+pub struct Perk {
+    pub name: String,
+    pub description: String,
+    pub minister: bool,
+}
+
+impl Perk {
+    fn from_json(json: &Value) -> Perk {
+        return Perk {
+            name: json["name"].as_str().unwrap_or("").to_owned(),
+            description: json["description"].as_str().unwrap_or("").to_owned(),
+            minister: json["minister"].as_bool().unwrap_or(false),
+        };
+    }
+
+    pub fn to_json(&self) -> Value {
+        return json!({
+            "name": self.name,
+            "description": self.description,
+            "minister": self.minister
+        });
+    }
+}
+
+fn get_perks(json: &Value) -> Vec<Perk> {
+    return json.as_array().map_or(Vec::new(), |perks| {
+        perks.iter().map(Perk::from_json).collect()
+    });
+}
+
+fn get_perks_list(perks: &[Perk]) -> Vec<Value> {
+    return perks.iter().map(|perk| perk.to_json()).collect();
+}
+
+// used for the mayor, the minister and every candidate, since they all share the same shape
+#[derive(Clone)]
+pub struct Candidate {
+    pub key: String,
+    pub name: String,
+    pub perks: Vec<Perk>,
+    pub votes: u64,
+}
+
+impl Candidate {
+    fn from_json(json: &Value) -> Option<Candidate> {
+        let mut perks = get_perks(&json["perks"]);
+        if json["perk"].is_object() {
+            perks.push(Perk::from_json(&json["perk"])); // ministers only have a single perk
+        }
+        return Some(Candidate {
+            key: json["key"].as_str()?.to_owned(),
+            name: json["name"].as_str()?.to_owned(),
+            perks,
+            votes: json["votes"].as_u64().unwrap_or(0),
+        });
+    }
+
+    pub fn to_json(&self) -> Value {
+        return json!({
+            "key": self.key,
+            "name": self.name,
+            "perks": get_perks_list(&self.perks)
+        });
+    }
+}
+
+#[derive(Clone)]
+pub struct Election {
+    pub year: u64,
+    pub candidates: Vec<Candidate>,
+}
+
+impl Election {
+    fn from_json(json: &Value) -> Option<Election> {
+        let candidates = json["candidates"].as_array()?;
+        return Some(Election {
+            year: json["year"].as_u64()?,
+            candidates: candidates.iter().filter_map(Candidate::from_json).collect(),
+        });
+    }
+
+    pub fn get_total_votes(&self) -> u64 {
+        return self
+            .candidates
+            .iter()
+            .map(|candidate| candidate.votes)
+            .sum();
+    }
+
+    pub fn get_leader(&self) -> Option<&Candidate> {
+        return self
+            .candidates
+            .iter()
+            .filter(|candidate| candidate.votes > 0)
+            .max_by_key(|candidate| candidate.votes);
+    }
+
+    pub fn to_json(&self) -> Value {
+        let total_votes = self.get_total_votes();
+        let mut candidates = Vec::new();
+        for candidate in self.candidates.iter() {
+            let mut json = candidate.to_json();
+            json["votes"] = json!(candidate.votes);
+            json["vote_share"] = json!(if total_votes > 0 {
+                candidate.votes as f64 / total_votes as f64
+            } else {
+                0.0
+            });
+            candidates.push(json);
+        }
+        return json!({
+            "year": self.year,
+            "total_votes": total_votes,
+            "candidates": candidates,
+            "projected_winner": self.get_leader().map(|leader| leader.key.to_owned())
+        });
+    }
+}
+
+pub struct ElectionState {
+    pub mayor: Option<Candidate>,
+    pub minister: Option<Candidate>,
+    pub last_election: Option<Election>, // the election the current mayor won, with its final results
+    pub current_election: Option<Election>, // only present while voting is open
+    pub last_updated: u64,
+}
+
+impl ElectionState {
+    pub fn get_term(&self) -> Option<(&str, u64)> {
+        let mayor = self.mayor.as_ref()?;
+        return Some((mayor.key.as_str(), self.last_election.as_ref()?.year));
+    }
+
+    pub fn to_json(&self) -> Value {
+        return json!({
+            "year": self.last_election.as_ref().map(|election| election.year),
+            "mayor": self.mayor.as_ref().map(|mayor| mayor.to_json()),
+            "minister": self.minister.as_ref().map(|minister| minister.to_json()),
+            "last_election": self.last_election.as_ref().map(|election| election.to_json()),
+            "current_election": self.current_election.as_ref().map(|election| election.to_json()),
+            "last_updated": self.last_updated
+        });
+    }
+}
+
 pub async fn get() -> MutexGuard<'static, HashSet<String>> {
     return PERKS.lock().await;
 }
 
-pub async fn get_perks_json() -> BoxBody {
+pub async fn get_state() -> MutexGuard<'static, ElectionState> {
+    return STATE.lock().await;
+}
+
+pub async fn get_perks_json() -> Vec<u8> {
     let set = get().await;
+    let state = get_state().await;
     let mut list = Vec::new();
+    let mut details = json!({});
     for perk in set.iter() {
         list.push(perk);
+        details[perk] = if PERK_CATALOGUE["perks"][perk].is_object() {
+            PERK_CATALOGUE["perks"][perk].to_owned()
+        } else {
+            // perks missing from the catalogue still get the description from the election data
+            let description = state
+                .mayor
+                .iter()
+                .chain(state.minister.iter())
+                .flat_map(|official| official.perks.iter())
+                .find(|entry| entry.name.eq(perk))
+                .map(|entry| entry.description.to_owned());
+            json!({
+                "mayor": None::<String>,
+                "description": description,
+                "effects": []
+            })
+        };
     }
     let json = json!({
-        "perks": list
+        "perks": list,
+        "details": details
+    });
+    return json.to_string().into_bytes();
+}
+
+pub async fn cache_perks() {
+    cache::store("perks", cache::JSON_MIME, get_perks_json().await).await;
+    let state = get_state().await.to_json();
+    cache::store("election", cache::JSON_MIME, state.to_string().into_bytes()).await;
+}
+
+fn get_candidate_keys(election: Option<&Election>) -> Vec<&str> {
+    return election.map_or(Vec::new(), |election| {
+        election
+            .candidates
+            .iter()
+            .map(|candidate| candidate.key.as_str())
+            .collect()
     });
-    return BoxBody::new(json.to_string());
+}
+
+fn get_leader_key(election: Option<&Election>) -> Option<&str> {
+    return election
+        .and_then(|election| election.get_leader())
+        .map(|leader| leader.key.as_str());
+}
+
+fn get_events(
+    previous_perks: &HashSet<String>,
+    perks: &HashSet<String>,
+    previous: &ElectionState,
+    state: &ElectionState,
+) -> Vec<Value> {
+    let mut events = Vec::new();
+    if !previous_perks.eq(perks) {
+        events.push(json!({
+            "type": "perks_changed",
+            "mayor": state.mayor.as_ref().map(|mayor| mayor.name.to_owned()),
+            "minister": state.minister.as_ref().map(|minister| minister.name.to_owned()),
+            "perks": perks,
+            "previous_perks": previous_perks
+        }));
+    }
+    let Some(current) = state.current_election.as_ref() else {
+        return events;
+    };
+    let previous_current = previous.current_election.as_ref();
+    if !get_candidate_keys(Some(current)).eq(&get_candidate_keys(previous_current)) {
+        let names: Vec<&str> = current
+            .candidates
+            .iter()
+            .map(|candidate| candidate.name.as_str())
+            .collect();
+        events.push(json!({
+            "type": "candidates_changed",
+            "year": current.year,
+            "candidates": names
+        }));
+    }
+    let leader = get_leader_key(Some(current));
+    let previous_leader = get_leader_key(previous_current);
+    if let (Some(leader), Some(previous_leader)) = (leader, previous_leader)
+        && leader != previous_leader
+    {
+        let get_name = |key: &str| -> String {
+            let candidate = current
+                .candidates
+                .iter()
+                .find(|candidate| candidate.key.eq(key));
+            return candidate.map_or(key.to_owned(), |candidate| candidate.name.to_owned());
+        };
+        events.push(json!({
+            "type": "leader_changed",
+            "year": current.year,
+            "leader": get_name(leader),
+            "previous_leader": get_name(previous_leader)
+        }));
+    }
+    return events;
+}
+
+fn get_embed(event: &Value) -> Value {
+    let get_list = |name: &str| -> String {
+        let list: Vec<&str> = event[name]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter_map(|entry| entry.as_str())
+            .collect();
+        return list.join(", ");
+    };
+    let (title, description) = match event["type"].as_str().unwrap() {
+        "perks_changed" => (
+            format!(
+                "New Mayor: {}",
+                event["mayor"].as_str().unwrap_or("Unknown")
+            ),
+            format!(
+                "Minister: {}\n\nPerks\n```{}```",
+                event["minister"].as_str().unwrap_or("None"),
+                get_list("perks")
+            ),
+        ),
+        "candidates_changed" => (
+            format!("Year {} Election Candidates", event["year"]),
+            format!("```{}```", get_list("candidates")),
+        ),
+        _ => (
+            format!("Year {} Election Leader Changed", event["year"]),
+            format!(
+                "**{}** has overtaken **{}**",
+                event["leader"].as_str().unwrap(),
+                event["previous_leader"].as_str().unwrap()
+            ),
+        ),
+    };
+    return json!({
+        "embeds": [
+            {
+                "title": title,
+                "description": description,
+                "color": 0x5ca0bf
+            }
+        ]
+    });
+}
+
+// events can be months apart, so the connection is only kept open by the stream's keepalive comments
+pub fn subscribe() -> BoxBody {
+    return util::event_stream(ELECTION_EVENTS.subscribe(), |_| true);
 }
 
 pub async fn refresh_perks() {
@@ -33,13 +348,40 @@ pub async fn refresh_perks() {
         );
     } else {
         if let Some(json) = util::parse_json(req.unwrap()) {
-            let mut set = get().await;
-            set.clear();
+            let mut perks = HashSet::new();
             for perk in json["mayor"]["perks"].as_array().unwrap() {
-                set.insert(perk["name"].as_str().unwrap().to_string());
+                perks.insert(perk["name"].as_str().unwrap().to_string());
             }
             if let Some(minister_perk) = json["mayor"]["minister"]["perk"]["name"].as_str() {
-                set.insert(minister_perk.to_string());
+                perks.insert(minister_perk.to_string());
+            }
+            let new_state = ElectionState {
+                mayor: Candidate::from_json(&json["mayor"]),
+                minister: Candidate::from_json(&json["mayor"]["minister"]),
+                last_election: Election::from_json(&json["mayor"]["election"]),
+                current_election: Election::from_json(&json["current"]),
+                last_updated: json["lastUpdated"].as_u64().unwrap_or(0),
+            };
+            let mut set = get().await;
+            let mut state = get_state().await;
+            // nothing is known before the first refresh, so it is not treated as a change
+            let events = if state.last_updated == 0 {
+                Vec::new()
+            } else {
+                get_events(&set, &perks, &state, &new_state)
+            };
+            // a term only completes once a different mayor takes office, perk rotations don't count
+            if state.last_updated != 0 && state.get_term() != new_state.get_term() {
+                history::record_term(&state).await;
+            }
+            *set = perks;
+            *state = new_state;
+            drop(set);
+            drop(state);
+            cache_perks().await;
+            for event in events {
+                util::send_webhooks("NF_API_ELECTION_WEBHOOKS", &get_embed(&event)).await;
+                let _ = ELECTION_EVENTS.send(event);
             }
         }
     }
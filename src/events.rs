@@ -0,0 +1,84 @@
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+
+use crate::{calendar, election, util};
+
+static EVENTS: LazyLock<Value> =
+    LazyLock::new(|| util::parse_json_str(include_str!("../data/events.json")));
+
+fn get_list(event: &Value, name: &str, default: &[u64]) -> Vec<u64> {
+    if let Some(list) = event[name].as_array() {
+        return list.iter().filter_map(|entry| entry.as_u64()).collect();
+    }
+    return default.to_vec();
+}
+
+// events with an interval follow real time, such as the Dark Auction starting at :55 every hour
+fn get_real_occurrence(event: &Value, now: u64) -> (u64, u64) {
+    let interval = event["interval"].as_u64().unwrap();
+    let offset = event["offset"].as_u64().unwrap_or(0);
+    let duration = event["duration"].as_u64().unwrap_or(0);
+    let mut start = now / interval * interval + offset;
+    if start > now {
+        start -= interval;
+    }
+    if start + duration <= now {
+        start += interval;
+    }
+    return (start, start + duration);
+}
+
+fn get_calendar_occurrence(event: &Value, now: u64) -> Option<(u64, u64)> {
+    let months = get_list(event, "months", &(1..=12).collect::<Vec<u64>>());
+    let days = get_list(event, "days", &[1]);
+    let duration = event["hours"].as_u64().unwrap_or(0) * calendar::HOUR_LENGTH;
+    // long events such as the election can still be running from the previous year
+    let year = calendar::SkyBlockDate::from_timestamp(now).year.max(2) - 1;
+    for year in year..year + 3 {
+        for month in months.iter() {
+            for day in days.iter() {
+                let start = calendar::get_date_start(year, *month, *day)?;
+                if start + duration > now {
+                    return Some((start, start + duration));
+                }
+            }
+        }
+    }
+    return None;
+}
+
+pub async fn get_events_json() -> BoxBody {
+    let perks = election::get().await.clone();
+    let now = util::get_timestamp() as u64;
+    let mut events = Vec::new();
+    for event in EVENTS["events"].as_array().unwrap() {
+        let perk = event["perk"].as_str();
+        if perk.is_some_and(|perk| !perks.contains(perk)) {
+            continue; // mayor events only happen while their perk is active
+        }
+        let occurrence = if event["interval"].is_u64() {
+            Some(get_real_occurrence(event, now))
+        } else {
+            get_calendar_occurrence(event, now)
+        };
+        if let Some((start, end)) = occurrence {
+            events.push(json!({
+                "key": event["key"],
+                "name": event["name"],
+                "start": start,
+                "end": end,
+                "active": start <= now && now < end,
+                "perk": perk
+            }));
+        }
+    }
+    events.sort_by_key(|event| event["start"].as_u64().unwrap());
+    let json = json!({
+        "timestamp": now,
+        "date": calendar::SkyBlockDate::from_timestamp(now).to_json(),
+        "events": events
+    });
+    return BoxBody::new(json.to_string());
+}
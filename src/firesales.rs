@@ -0,0 +1,83 @@
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{pricing, util};
+
+static FIRE_SALES: LazyLock<Mutex<Option<Vec<Value>>>> = LazyLock::new(|| Mutex::new(None));
+
+pub async fn get() -> MutexGuard<'static, Option<Vec<Value>>> {
+    return FIRE_SALES.lock().await;
+}
+
+fn is_same_sale(a: &Value, b: &Value) -> bool {
+    return a["item_id"] == b["item_id"] && a["start"] == b["start"];
+}
+
+fn get_embed(sale: &Value) -> Value {
+    return json!({
+        "embeds": [
+            {
+                "title": format!("New Fire Sale: {}", sale["item_id"].as_str().unwrap_or("")),
+                "description": format!("Starts: <t:{}:R>\nEnds: <t:{}:R>\nAmount: {}\nPrice: {} Gems",
+                    sale["start"].as_u64().unwrap_or(0) / 1000,
+                    sale["end"].as_u64().unwrap_or(0) / 1000,
+                    sale["amount"],
+                    sale["price"]
+                ),
+                "color": 0x5ca0bf
+            }
+        ]
+    });
+}
+
+pub async fn get_fire_sales_json() -> BoxBody {
+    let sales = get().await.to_owned().unwrap_or_default();
+    let map = pricing::get().await;
+    let now = util::get_timestamp() as u64;
+    let mut list = Vec::new();
+    for sale in sales.iter() {
+        let start = sale["start"].as_u64().unwrap_or(0);
+        let end = sale["end"].as_u64().unwrap_or(0);
+        if end <= now {
+            continue;
+        }
+        let item_id = sale["item_id"].as_str().unwrap_or("");
+        list.push(json!({
+            "item_id": item_id,
+            "start": start,
+            "end": end,
+            "amount": sale["amount"],
+            "price": sale["price"],
+            "active": start <= now,
+            "lowest_bin": pricing::get_auction_price(&map, item_id)
+        }));
+    }
+    let json = json!({ "sales": list });
+    return BoxBody::new(json.to_string());
+}
+
+pub async fn refresh_fire_sales() {
+    let req = util::make_request("v2/skyblock/firesales").await;
+    if req.is_err() {
+        println!(
+            "Panicked while refreshing fire sale data:\n{}",
+            req.unwrap_err()
+        );
+    } else {
+        if let Some(json) = util::parse_json(req.unwrap()) {
+            let sales = json["sales"]
+                .as_array()
+                .map_or(Vec::new(), |sales| sales.to_owned());
+            let mut current = get().await;
+            let announced = util::get_new_entries(current.as_ref(), &sales, is_same_sale);
+            *current = Some(sales);
+            drop(current);
+            for sale in announced.iter() {
+                util::send_webhooks("NF_API_FIRESALE_WEBHOOKS", &get_embed(sale)).await;
+            }
+        }
+    }
+}
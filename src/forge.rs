@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+use tokio::sync::MutexGuard;
+
+use crate::{pricing, util};
+
+static FORGE: LazyLock<Value> =
+    LazyLock::new(|| util::parse_json_str(include_str!("../data/forge.json")));
+
+fn get_quick_forge_reduction(level: u32) -> f64 {
+    if level == 0 {
+        return 0.0;
+    }
+    if level >= 20 {
+        return 0.3; // the final level jumps straight from 19.5% to 30%
+    }
+    return 0.1 + 0.005 * level as f64;
+}
+
+pub fn get_recipe_cost(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    item_id: &str,
+) -> Option<f64> {
+    let recipe = FORGE["recipes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|recipe| recipe["id"].as_str().unwrap().eq(item_id))?;
+    return get_cost(map, recipe);
+}
+
+// None if any input has no price yet, since counting it as free would understate the cost
+fn get_cost(map: &MutexGuard<'static, HashMap<String, Value>>, recipe: &Value) -> Option<f64> {
+    let mut cost = recipe["coins"].as_f64().unwrap();
+    for (input_id, amount) in recipe["inputs"].as_object().unwrap() {
+        cost += pricing::get_buy_price(map, input_id)? * amount.as_f64().unwrap();
+    }
+    return Some(cost);
+}
+
+pub async fn get_profits_json(query: &HashMap<String, String>) -> BoxBody {
+    let map = pricing::get().await;
+    let quick_forge = query
+        .get("quick_forge")
+        .and_then(|level| level.parse::<u32>().ok())
+        .unwrap_or(0);
+    let hotm = query.get("hotm").and_then(|tier| tier.parse::<u64>().ok());
+    let reduction = get_quick_forge_reduction(quick_forge);
+    let mut results = Vec::new();
+    for recipe in FORGE["recipes"].as_array().unwrap() {
+        if hotm.is_some_and(|tier| recipe["hotm"].as_u64().unwrap() > tier) {
+            continue;
+        }
+        let id = recipe["id"].as_str().unwrap();
+        let mut inputs = json!({});
+        for (item_id, amount) in recipe["inputs"].as_object().unwrap() {
+            inputs[item_id] = json!({
+                "amount": amount,
+                "price": pricing::get_buy_price(&map, item_id)
+            });
+        }
+        let cost = get_cost(&map, recipe);
+        let value = pricing::get_sell_price(&map, id);
+        let duration = recipe["duration"].as_f64().unwrap() * (1.0 - reduction);
+        let profit = cost.zip(value).map(|(cost, value)| value - cost);
+        results.push(json!({
+            "id": id,
+            "inputs": inputs,
+            "coins": recipe["coins"],
+            "hotm": recipe["hotm"],
+            "cost": cost,
+            "value": value,
+            "profit": profit,
+            "duration": duration,
+            "profit_per_hour": profit.map(|profit| profit / duration),
+            "complete": profit.is_some()
+        }));
+    }
+    // recipes with unpriced inputs or outputs can't be ranked, so they go last
+    results.sort_by(|a, b| {
+        let a = a["profit_per_hour"].as_f64().unwrap_or(f64::NEG_INFINITY);
+        let b = b["profit_per_hour"].as_f64().unwrap_or(f64::NEG_INFINITY);
+        return b.total_cmp(&a);
+    });
+    let json = json!({
+        "quick_forge": quick_forge,
+        "recipes": results
+    });
+    return BoxBody::new(json.to_string());
+}
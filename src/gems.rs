@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use actix_web::body::BoxBody;
+use crab_nbt::{NbtCompound, NbtTag};
+use serde_json::{Value, json};
+use tokio::sync::MutexGuard;
+
+use crate::{items, pricing, upgrades, util};
+
+const UNIVERSAL_SLOTS: [&str; 6] = [
+    "COMBAT",
+    "OFFENSIVE",
+    "DEFENSIVE",
+    "UNIVERSAL",
+    "MINING",
+    "CHISEL",
+];
+
+pub struct Gem {
+    pub slot: String,
+    pub slot_type: String,
+    pub index: usize,
+    pub gem: String,
+    pub quality: String,
+}
+
+impl Gem {
+    pub fn get_item_id(&self) -> String {
+        return format!("{}_{}_GEM", self.quality, self.gem);
+    }
+}
+
+fn get_quality(tag: &NbtTag) -> Option<String> {
+    if let Some(quality) = tag.extract_string() {
+        return Some(quality.to_owned());
+    }
+    return Some(tag.extract_compound()?.get_string("quality")?.to_owned());
+}
+
+pub fn decode_gems(extra: &NbtCompound) -> Vec<Gem> {
+    let mut gems = Vec::new();
+    if let Some(compound) = extra.get_compound("gems") {
+        for (slot, tag) in compound.child_tags.iter() {
+            if slot.ends_with("_gem") || slot.eq("unlocked_slots") {
+                continue;
+            }
+            let Some((slot_type, index)) = slot.rsplit_once('_') else {
+                continue;
+            };
+            let gem = if UNIVERSAL_SLOTS.contains(&slot_type) {
+                // universal slots store the gemstone type in a separate "<slot>_gem" tag
+                compound.get_string(&format!("{}_gem", slot)).cloned()
+            } else {
+                Some(slot_type.to_owned())
+            };
+            if let (Some(gem), Some(quality)) = (gem, get_quality(tag)) {
+                gems.push(Gem {
+                    slot: slot.to_owned(),
+                    slot_type: slot_type.to_owned(),
+                    index: index.parse().unwrap_or(0),
+                    gem,
+                    quality,
+                });
+            }
+        }
+    }
+    return gems;
+}
+
+fn get_unlocked_slots(extra: &NbtCompound) -> Vec<String> {
+    let mut slots = Vec::new();
+    if let Some(compound) = extra.get_compound("gems") {
+        for tag in compound.get_list("unlocked_slots").unwrap_or(&Vec::new()) {
+            if let Some(slot) = tag.extract_string() {
+                slots.push(slot.to_owned());
+            }
+        }
+    }
+    return slots;
+}
+
+fn get_slot_costs(item_data: &Value, slot: &str) -> Value {
+    let Some((slot_type, index)) = slot.rsplit_once('_') else {
+        return Value::Null;
+    };
+    let index: usize = index.parse().unwrap_or(0);
+    let slot_data = item_data["gemstone_slots"]
+        .as_array()
+        .unwrap_or(&Vec::new())
+        .iter()
+        .filter(|data| data["slot_type"].as_str().is_some_and(|t| t.eq(slot_type)))
+        .nth(index)
+        .cloned();
+    return slot_data.map_or(Value::Null, |data| data["costs"].to_owned());
+}
+
+pub fn get_gems_value(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    extra: &NbtCompound,
+    item_data: &Value,
+) -> Value {
+    let mut gems = Vec::new();
+    let mut gem_value = 0.0;
+    for gem in decode_gems(extra) {
+        let id = gem.get_item_id();
+        let price = pricing::get_sell_price(map, &id);
+        gem_value += price.unwrap_or(0.0);
+        gems.push(json!({
+            "slot": gem.slot,
+            "slot_type": gem.slot_type,
+            "index": gem.index,
+            "gem": gem.gem,
+            "quality": gem.quality,
+            "id": id,
+            "price": price
+        }));
+    }
+    let mut unlocked = Vec::new();
+    let mut unlock_cost = 0.0;
+    for slot in get_unlocked_slots(extra) {
+        let (costs, cost) = upgrades::get_costs_price(map, &get_slot_costs(item_data, &slot));
+        unlock_cost += cost;
+        unlocked.push(json!({
+            "slot": slot,
+            "costs": costs,
+            "cost": cost
+        }));
+    }
+    return json!({
+        "gems": gems,
+        "gem_value": gem_value,
+        "unlocked_slots": unlocked,
+        "unlock_cost": unlock_cost,
+        "value": gem_value + unlock_cost
+    });
+}
+
+pub async fn get_gem_value_json(body: &Value) -> Option<BoxBody> {
+    let nbt = util::parse_item_nbt(body["item_bytes"].as_str()?).await?;
+    let extra = nbt.get_compound("tag")?.get_compound("ExtraAttributes")?;
+    let base_id = extra.get_string("id")?;
+    let item_id = pricing::get_item_id(extra)?;
+    let item_data = items::get_data()
+        .await
+        .get(base_id)
+        .map(|data| data.to_owned())
+        .unwrap_or(json!({}));
+    let map = pricing::get().await;
+    let mut json = get_gems_value(&map, extra, &item_data);
+    json["id"] = json!(item_id);
+    return Some(BoxBody::new(json.to_string()));
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::{env, fs, io::ErrorKind, sync::LazyLock};
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::election::ElectionState;
+
+// None when the archive exists but can't be read, which keeps it from being overwritten
+static HISTORY: LazyLock<Mutex<Option<Vec<Value>>>> = LazyLock::new(|| Mutex::new(load_history()));
+
+const PAGE_SIZE: usize = 20;
+
+fn get_path() -> String {
+    return env::var("NF_API_ELECTION_HISTORY").unwrap_or("election_history.json".to_string());
+}
+
+fn load_history() -> Option<Vec<Value>> {
+    let contents = match fs::read_to_string(get_path()) {
+        Ok(contents) => contents,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Some(Vec::new()),
+        Err(error) => {
+            println!("Panicked while reading election history:\n{}", error);
+            return None;
+        }
+    };
+    match serde_json::from_str(&contents) {
+        Ok(Value::Array(terms)) => return Some(terms),
+        _ => {
+            println!("Panicked while parsing election history, it will not be overwritten");
+            return None;
+        }
+    }
+}
+
+// writes to a temporary file first, so an interrupted write can't leave a truncated archive behind
+fn write_history(history: &[Value]) -> std::io::Result<()> {
+    let path = get_path();
+    let temp_path = format!("{}.tmp", path);
+    fs::write(&temp_path, json!(history).to_string())?;
+    return fs::rename(&temp_path, &path);
+}
+
+pub async fn get() -> MutexGuard<'static, Option<Vec<Value>>> {
+    return HISTORY.lock().await;
+}
+
+// called with the outgoing state once a new mayor takes office, so only completed terms are archived
+pub async fn record_term(state: &ElectionState) {
+    let (Some(mayor), Some(election)) = (state.mayor.as_ref(), state.last_election.as_ref()) else {
+        return;
+    };
+    let mut perks: Vec<Value> = mayor.perks.iter().map(|perk| perk.to_json()).collect();
+    if let Some(minister) = state.minister.as_ref() {
+        perks.extend(minister.perks.iter().map(|perk| perk.to_json()));
+    }
+    let results = election.to_json();
+    let term = json!({
+        "year": election.year,
+        "mayor": {
+            "key": mayor.key,
+            "name": mayor.name
+        },
+        "minister": state.minister.as_ref().map(|minister| json!({
+            "key": minister.key,
+            "name": minister.name
+        })),
+        "perks": perks,
+        "total_votes": results["total_votes"],
+        "results": results["candidates"]
+    });
+    let mut guard = get().await;
+    let Some(history) = guard.as_mut() else {
+        println!("Panicked while archiving election term, the history file could not be loaded");
+        return;
+    };
+    if history.iter().any(|entry| entry["year"] == term["year"]) {
+        return;
+    }
+    history.push(term);
+    history.sort_by_key(|entry| std::cmp::Reverse(entry["year"].as_u64().unwrap_or(0)));
+    if let Err(error) = write_history(history) {
+        println!("Panicked while writing election history:\n{}", error);
+    }
+}
+
+pub async fn get_history_json(query: &HashMap<String, String>) -> BoxBody {
+    let guard = get().await;
+    let history = guard.as_deref().unwrap_or_default();
+    let mayor = query.get("mayor").map(|mayor| mayor.to_lowercase());
+    let terms: Vec<&Value> = history
+        .iter()
+        .filter(|term| {
+            mayor.as_ref().is_none_or(|mayor| {
+                let key = term["mayor"]["key"].as_str().unwrap_or("");
+                let name = term["mayor"]["name"].as_str().unwrap_or("");
+                return key.eq(mayor) || name.to_lowercase().eq(mayor);
+            })
+        })
+        .collect();
+    let page = query
+        .get("page")
+        .and_then(|page| page.parse::<usize>().ok())
+        .unwrap_or(0);
+    let json = json!({
+        "page": page,
+        "total_pages": terms.len().div_ceil(PAGE_SIZE),
+        "total_terms": terms.len(),
+        "terms": terms.iter().skip(page * PAGE_SIZE).take(PAGE_SIZE).collect::<Vec<_>>()
+    });
+    return BoxBody::new(json.to_string());
+}
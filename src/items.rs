@@ -1,18 +1,26 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::LazyLock;
 
-use actix_web::body::BoxBody;
 use serde_json::{Value, json};
 use tokio::sync::{Mutex, MutexGuard};
 
+use crate::cache;
+
 static NON_PLACEABLE: LazyLock<Mutex<HashSet<String>>> =
     LazyLock::new(|| Mutex::new(HashSet::new()));
 
+static ITEM_DATA: LazyLock<Mutex<HashMap<String, Value>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
 pub async fn get() -> MutexGuard<'static, HashSet<String>> {
     return NON_PLACEABLE.lock().await;
 }
 
-pub async fn get_attributes_json() -> BoxBody {
+pub async fn get_data() -> MutexGuard<'static, HashMap<String, Value>> {
+    return ITEM_DATA.lock().await;
+}
+
+pub async fn get_attributes_json() -> Vec<u8> {
     let set = get().await;
     let mut list = Vec::new();
     for perk in set.iter() {
@@ -21,18 +29,37 @@ pub async fn get_attributes_json() -> BoxBody {
     let json = json!({
         "non_placeable": list
     });
-    return BoxBody::new(json.to_string());
+    return json.to_string().into_bytes();
+}
+
+pub async fn cache_attributes() {
+    cache::store("attributes", cache::JSON_MIME, get_attributes_json().await).await;
 }
 
 pub async fn refresh_items(json: &Value) {
     let mut set = get().await;
+    let mut data = get_data().await;
     set.clear();
+    data.clear();
     let items = json["items"].as_array().unwrap();
     for item in items {
+        let id = item["id"].as_str().unwrap();
         let can_place = item["can_place"].as_bool();
         if can_place.is_some() && !can_place.unwrap() {
-            let id = item["id"].as_str().unwrap();
             set.insert(id.to_owned());
         }
+        data.insert(
+            id.to_owned(),
+            json!({
+                "tier": item["tier"],
+                "category": item["category"],
+                "dungeon_item": item["dungeon_item"].as_bool().unwrap_or(false),
+                "upgrade_costs": item["upgrade_costs"],
+                "gemstone_slots": item["gemstone_slots"]
+            }),
+        );
     }
+    drop(set);
+    drop(data);
+    cache_attributes().await;
 }
@@ -1,9 +1,30 @@
+mod accessories;
+mod anomalies;
+mod auctions;
+mod bingo;
+mod cache;
+mod calendar;
+mod chests;
+mod currency;
+mod dictionary;
 mod election;
+mod events;
+mod firesales;
+mod forge;
+mod gems;
+mod history;
 mod items;
 mod limiter;
+mod minions;
+mod news;
 mod pricing;
+mod reforges;
+mod resources;
+mod sniper;
 mod tracking;
+mod upgrades;
 mod util;
+mod valuation;
 
 use actix_web::{
     App, HttpRequest, HttpServer, Responder,
@@ -12,21 +33,21 @@ use actix_web::{
     get,
     http::{
         StatusCode,
-        header::{CONTENT_TYPE, HeaderName, HeaderValue},
+        header::{CACHE_CONTROL, CONTENT_TYPE, HeaderName, HeaderValue},
     },
     middleware,
-    mime::APPLICATION_JSON,
+    mime::{APPLICATION_JSON, TEXT_EVENT_STREAM},
     post,
-    web::{Bytes, PayloadConfig},
+    web::{Bytes, PayloadConfig, Query},
 };
 use serde_json::{Value, json};
 use std::{
+    collections::{HashMap, HashSet},
     env, fs,
     sync::LazyLock,
     time::{Duration, SystemTime},
 };
 use tokio::{task, time::sleep};
-use ureq::{Agent, AsSendBody};
 
 static BETA_AUTH: LazyLock<String> =
     LazyLock::new(|| env::var("NF_API_BETA_AUTH").unwrap_or(String::new()));
@@ -61,43 +82,390 @@ fn response_ok(body: BoxBody) -> Response<BoxBody> {
     return res;
 }
 
-async fn http_post(
-    url: String,
-    body: impl AsSendBody,
-) -> Result<ureq::http::Response<ureq::Body>, ureq::Error> {
-    Agent::new_with_defaults()
-        .post(url)
-        .header("Content-Type", "application/json")
-        .send(body)
+fn response_msgpack(body: BoxBody) -> Response<BoxBody> {
+    let mut res = Response::new(StatusCode::OK).set_body(body);
+    res.headers_mut()
+        .append(CONTENT_TYPE, HeaderValue::from_static(cache::MSGPACK_MIME));
+    return res;
+}
+
+fn response_stream(body: BoxBody) -> Response<BoxBody> {
+    let mut res = Response::new(StatusCode::OK).set_body(body);
+    res.headers_mut().append(
+        CONTENT_TYPE,
+        HeaderValue::from_static(TEXT_EVENT_STREAM.essence_str()),
+    );
+    res.headers_mut()
+        .append(CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+    return res;
+}
+
+fn get_query_list(query: &HashMap<String, String>, name: &str) -> HashSet<String> {
+    if let Some(value) = query.get(name) {
+        return value
+            .split(',')
+            .filter(|entry| !entry.is_empty())
+            .map(|entry| entry.to_uppercase())
+            .collect();
+    }
+    return HashSet::new();
+}
+
+// json is the default, so msgpack is only sent when it's preferred over it
+fn accepts_msgpack(req: &HttpRequest) -> bool {
+    let accept = get_header(req, "accept");
+    let msgpack = cache::get_quality(&accept, &[cache::MSGPACK_MIME, "application/x-msgpack"]);
+    let json = cache::get_quality(&accept, &[APPLICATION_JSON.essence_str()]);
+    return msgpack.is_some_and(|msgpack| msgpack > 0.0 && msgpack > json.unwrap_or(0.0));
 }
 
 #[get("/v2/economy/get-item-pricing/")]
-async fn get_item_pricing_v2(req: HttpRequest) -> impl Responder {
-    let key = limiter::new_key("get-item-pricing", req).await;
+async fn get_item_pricing_v2(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-item-pricing", req.clone()).await;
     if limiter::is_limited(&key, 30000, 1).await {
         return Response::new(StatusCode::TOO_MANY_REQUESTS);
     }
     tracking::add_usage("pricing").await;
-    return response_ok(pricing::get_pricing_json().await);
+    if let Some(filter) = pricing::PricingFilter::from_query(&query) {
+        if accepts_msgpack(&req) {
+            let bytes = pricing::get_pricing_msgpack(Some(&filter)).await;
+            return response_msgpack(BoxBody::new(bytes));
+        }
+        return response_ok(BoxBody::new(pricing::get_pricing_json(Some(&filter)).await));
+    }
+    if accepts_msgpack(&req) {
+        return cache::respond(&req, "pricing-msgpack").await;
+    }
+    return cache::respond(&req, "pricing").await;
+}
+
+#[get("/v2/economy/get-item-dictionary/")]
+async fn get_item_dictionary(req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("get-item-dictionary", req).await;
+    if limiter::is_limited(&key, 30000, 1).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return response_ok(dictionary::get_dictionary_json().await);
+}
+
+#[get("/v1/auction/get-snipe-feed/")]
+async fn get_snipe_feed(req: HttpRequest, query: Query<HashMap<String, String>>) -> impl Responder {
+    let key = limiter::new_key("get-snipe-feed", req).await;
+    if limiter::is_limited(&key, 30000, 1).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    let min_profit = query
+        .get("min_profit")
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0.0);
+    let items = get_query_list(&query, "items");
+    return response_stream(sniper::subscribe(min_profit, items));
+}
+
+#[get("/v1/auction/search-auctions/")]
+async fn search_auctions(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("search-auctions", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return response_ok(auctions::search_auctions_json(&query).await);
+}
+
+#[get("/v1/auction/get-player-auctions/")]
+async fn get_player_auctions(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-player-auctions", req).await;
+    if limiter::is_limited(&key, 10000, 2).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    let uuid = query
+        .get("uuid")
+        .map(|uuid| uuid.replace('-', "").to_lowercase())
+        .unwrap_or_default();
+    if !auctions::is_valid_uuid(&uuid) {
+        return Response::new(StatusCode::BAD_REQUEST);
+    }
+    match auctions::get_player_auctions_json(&uuid).await {
+        Ok(body) => return response_ok(body),
+        Err(status) => return Response::new(status),
+    }
+}
+
+#[get("/v1/economy/get-minion-profits/")]
+async fn get_minion_profits(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-minion-profits", req).await;
+    if limiter::is_limited(&key, 10000, 2).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    if let Some(body) = minions::get_profits_json(&query).await {
+        return response_ok(body);
+    }
+    return Response::new(StatusCode::BAD_REQUEST);
+}
+
+#[get("/v1/economy/get-forge-profits/")]
+async fn get_forge_profits(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-forge-profits", req).await;
+    if limiter::is_limited(&key, 10000, 2).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return response_ok(forge::get_profits_json(&query).await);
+}
+
+#[post("/v1/economy/post-chest-value/")]
+async fn post_chest_value(payload: Bytes, req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("post-chest-value", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    let json: Result<Value, serde_json::Error> = serde_json::from_slice(&payload);
+    if json.is_err() {
+        return Response::new(StatusCode::BAD_REQUEST);
+    }
+    if let Some(body) = chests::get_chest_value_json(&json.unwrap()).await {
+        return response_ok(body);
+    }
+    return Response::new(StatusCode::BAD_REQUEST);
+}
+
+#[get("/v1/economy/get-upgrade-cost/")]
+async fn get_upgrade_cost(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-upgrade-cost", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    if let Some(body) = upgrades::get_upgrade_cost_json(&query).await {
+        return response_ok(body);
+    }
+    return Response::new(StatusCode::BAD_REQUEST);
+}
+
+#[get("/v1/economy/get-reforge-cost/")]
+async fn get_reforge_cost(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-reforge-cost", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    if let Some(body) = reforges::get_reforge_cost_json(&query).await {
+        return response_ok(body);
+    }
+    return Response::new(StatusCode::BAD_REQUEST);
+}
+
+#[post("/v1/economy/post-item-value/")]
+async fn post_item_value(payload: Bytes, req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("post-item-value", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    let json: Result<Value, serde_json::Error> = serde_json::from_slice(&payload);
+    if json.is_err() {
+        return Response::new(StatusCode::BAD_REQUEST);
+    }
+    if let Some(body) = valuation::get_item_value_json(&json.unwrap()).await {
+        return response_ok(body);
+    }
+    return Response::new(StatusCode::BAD_REQUEST);
+}
+
+#[post("/v1/economy/post-gem-value/")]
+async fn post_gem_value(payload: Bytes, req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("post-gem-value", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    let json: Result<Value, serde_json::Error> = serde_json::from_slice(&payload);
+    if json.is_err() {
+        return Response::new(StatusCode::BAD_REQUEST);
+    }
+    if let Some(body) = gems::get_gem_value_json(&json.unwrap()).await {
+        return response_ok(body);
+    }
+    return Response::new(StatusCode::BAD_REQUEST);
+}
+
+#[get("/v1/economy/get-currency-values/")]
+async fn get_currency_values(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-currency-values", req).await;
+    if limiter::is_limited(&key, 10000, 2).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    if let Some(body) = currency::get_currency_values_json(&query).await {
+        return response_ok(body);
+    }
+    return Response::new(StatusCode::BAD_REQUEST);
+}
+
+#[post("/v1/economy/post-accessory-upgrades/")]
+async fn post_accessory_upgrades(payload: Bytes, req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("post-accessory-upgrades", req).await;
+    if limiter::is_limited(&key, 10000, 2).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    let json: Result<Value, serde_json::Error> = serde_json::from_slice(&payload);
+    if json.is_err() {
+        return Response::new(StatusCode::BAD_REQUEST);
+    }
+    if let Some(body) = accessories::get_upgrades_json(&json.unwrap()).await {
+        return response_ok(body);
+    }
+    return Response::new(StatusCode::BAD_REQUEST);
+}
+
+#[get("/v1/economy/get-bazaar-anomalies/")]
+async fn get_bazaar_anomalies(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-bazaar-anomalies", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return response_ok(anomalies::get_anomalies_json(&query).await);
+}
+
+#[get("/v1/election/get-state/")]
+async fn get_election_state(req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("get-election-state", req.clone()).await;
+    if limiter::is_limited(&key, 30000, 1).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return cache::respond(&req, "election").await;
+}
+
+#[get("/v1/calendar/get-date/")]
+async fn get_calendar_date(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-calendar-date", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    if let Some(body) = calendar::get_date_json(&query) {
+        return response_ok(body);
+    }
+    return Response::new(StatusCode::BAD_REQUEST);
+}
+
+#[get("/v1/calendar/get-events/")]
+async fn get_calendar_events(req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("get-calendar-events", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return response_ok(events::get_events_json().await);
+}
+
+#[get("/v1/election/get-history/")]
+async fn get_election_history(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-election-history", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return response_ok(history::get_history_json(&query).await);
+}
+
+#[get("/v1/election/get-event-feed/")]
+async fn get_election_feed(req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("get-election-feed", req).await;
+    if limiter::is_limited(&key, 30000, 1).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return response_stream(election::subscribe());
+}
+
+#[get("/v1/economy/get-fire-sales/")]
+async fn get_fire_sales(req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("get-fire-sales", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return response_ok(firesales::get_fire_sales_json().await);
+}
+
+#[get("/v1/misc/get-news/")]
+async fn get_news(req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("get-news", req.clone()).await;
+    if limiter::is_limited(&key, 30000, 1).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return cache::respond(&req, "news").await;
+}
+
+#[get("/v1/bingo/get-goals/")]
+async fn get_bingo_goals(req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("get-bingo-goals", req.clone()).await;
+    if limiter::is_limited(&key, 30000, 1).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return cache::respond(&req, "bingo").await;
+}
+
+#[get("/v1/resources/get-collections/")]
+async fn get_collections(
+    req: HttpRequest,
+    query: Query<HashMap<String, String>>,
+) -> impl Responder {
+    let key = limiter::new_key("get-collections", req).await;
+    if limiter::is_limited(&key, 10000, 5).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return response_ok(resources::get_collections_json(&query).await);
+}
+
+#[get("/v1/resources/get-skills/")]
+async fn get_skills(req: HttpRequest) -> impl Responder {
+    let key = limiter::new_key("get-skills", req.clone()).await;
+    if limiter::is_limited(&key, 30000, 1).await {
+        return Response::new(StatusCode::TOO_MANY_REQUESTS);
+    }
+    return cache::respond(&req, "skills").await;
 }
 
 #[get("/v1/election/get-active-perks/")]
 async fn get_active_perks(req: HttpRequest) -> impl Responder {
-    let key = limiter::new_key("get-active-perks", req).await;
+    let key = limiter::new_key("get-active-perks", req.clone()).await;
     if limiter::is_limited(&key, 30000, 1).await {
         return Response::new(StatusCode::TOO_MANY_REQUESTS);
     }
     tracking::add_usage("perks").await;
-    return response_ok(election::get_perks_json().await);
+    return cache::respond(&req, "perks").await;
 }
 
 #[get("/v1/misc/get-item-attributes/")]
 async fn get_item_attributes(req: HttpRequest) -> impl Responder {
-    let key = limiter::new_key("get-item-attributes", req).await;
+    let key = limiter::new_key("get-item-attributes", req.clone()).await;
     if limiter::is_limited(&key, 30000, 1).await {
         return Response::new(StatusCode::TOO_MANY_REQUESTS);
     }
-    return response_ok(items::get_attributes_json().await);
+    return cache::respond(&req, "attributes").await;
 }
 
 #[get("/v1/misc/get-api-usage/")]
@@ -164,7 +532,7 @@ async fn post_beta_build(payload: Bytes, req: HttpRequest) -> impl Responder {
                     }
                 ]
             });
-            let _ = http_post(webhook.unwrap(), message.to_string()).await;
+            let _ = util::http_post(webhook.unwrap(), message.to_string()).await;
             return Response::ok();
         }
     }
@@ -174,6 +542,12 @@ async fn post_beta_build(payload: Bytes, req: HttpRequest) -> impl Responder {
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     util::load_env_file();
+    pricing::cache_pricing().await; // serve empty payloads until the first refreshes finish
+    election::cache_perks().await;
+    items::cache_attributes().await;
+    news::cache_news().await;
+    bingo::cache_bingo().await;
+    resources::cache_skills().await;
 
     task::spawn(async {
         let duration = Duration::from_millis(240000);
@@ -207,6 +581,35 @@ async fn main() -> std::io::Result<()> {
         }
     });
 
+    task::spawn(async {
+        let duration = Duration::from_millis(1800000);
+        loop {
+            let req = util::make_request("v2/resources/skyblock/collections").await;
+            if req.is_err() {
+                println!(
+                    "Panicked while refreshing collection data:\n{}",
+                    req.unwrap_err()
+                );
+            } else {
+                if let Some(json) = util::parse_json(req.unwrap()) {
+                    resources::refresh_collections(&json).await;
+                }
+            }
+            let req = util::make_request("v2/resources/skyblock/skills").await;
+            if req.is_err() {
+                println!(
+                    "Panicked while refreshing skill data:\n{}",
+                    req.unwrap_err()
+                );
+            } else {
+                if let Some(json) = util::parse_json(req.unwrap()) {
+                    resources::refresh_skills(&json).await;
+                }
+            }
+            sleep(duration).await;
+        }
+    });
+
     task::spawn(async {
         let duration = Duration::from_millis(180000);
         loop {
@@ -215,6 +618,30 @@ async fn main() -> std::io::Result<()> {
         }
     });
 
+    task::spawn(async {
+        let duration = Duration::from_millis(600000);
+        loop {
+            bingo::refresh_bingo().await;
+            sleep(duration).await;
+        }
+    });
+
+    task::spawn(async {
+        let duration = Duration::from_millis(300000);
+        loop {
+            firesales::refresh_fire_sales().await;
+            sleep(duration).await;
+        }
+    });
+
+    task::spawn(async {
+        let duration = Duration::from_millis(900000);
+        loop {
+            news::refresh_news().await;
+            sleep(duration).await;
+        }
+    });
+
     task::spawn(async {
         let duration = Duration::from_millis(3600000);
         loop {
@@ -248,6 +675,30 @@ async fn main() -> std::io::Result<()> {
             ))
             .app_data(PayloadConfig::new(10000000))
             .service(get_item_pricing_v2)
+            .service(get_item_dictionary)
+            .service(get_snipe_feed)
+            .service(search_auctions)
+            .service(get_player_auctions)
+            .service(get_minion_profits)
+            .service(get_forge_profits)
+            .service(post_chest_value)
+            .service(get_upgrade_cost)
+            .service(get_reforge_cost)
+            .service(post_item_value)
+            .service(post_gem_value)
+            .service(get_currency_values)
+            .service(post_accessory_upgrades)
+            .service(get_bazaar_anomalies)
+            .service(get_election_state)
+            .service(get_calendar_date)
+            .service(get_calendar_events)
+            .service(get_election_history)
+            .service(get_election_feed)
+            .service(get_fire_sales)
+            .service(get_news)
+            .service(get_bingo_goals)
+            .service(get_collections)
+            .service(get_skills)
             .service(get_active_perks)
             .service(get_item_attributes)
             .service(get_api_usage)
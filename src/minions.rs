@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+use tokio::sync::MutexGuard;
+
+use crate::{pricing, util};
+
+static MINIONS: LazyLock<Value> =
+    LazyLock::new(|| util::parse_json_str(include_str!("../data/minions.json")));
+
+const MAX_LIMIT: usize = 500;
+
+struct Profit {
+    minion: &'static Value,
+    tier: usize,
+    fuel: &'static Value,
+    upgrades: [&'static Value; 2],
+    fuel_cost: f64,
+    coins_per_hour: f64,
+}
+
+fn get_drop_price(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    item_id: &str,
+    sell: &str,
+) -> f64 {
+    if sell.eq("npc") {
+        return pricing::get_npc_price(map, item_id).unwrap_or(0.0);
+    }
+    return pricing::get_bazaar_price(map, item_id, "sell")
+        .or(pricing::get_npc_price(map, item_id))
+        .unwrap_or(0.0);
+}
+
+// copies every price the calculation needs, so the pricing lock isn't held while ranking
+async fn get_prices(sell: &str) -> (HashMap<&'static str, f64>, HashMap<&'static str, f64>) {
+    let map = pricing::get().await;
+    let mut drop_prices = HashMap::new();
+    drop_prices.insert("DIAMOND", get_drop_price(&map, "DIAMOND", sell));
+    for minion in MINIONS["minions"].as_array().unwrap() {
+        for item_id in minion["drops"].as_object().unwrap().keys() {
+            drop_prices.insert(item_id.as_str(), get_drop_price(&map, item_id, sell));
+        }
+    }
+    let mut fuel_prices = HashMap::new();
+    for fuel in MINIONS["fuels"].as_array().unwrap() {
+        let fuel_id = fuel["id"].as_str().unwrap();
+        fuel_prices.insert(
+            fuel_id,
+            pricing::get_buy_price(&map, fuel_id).unwrap_or(0.0),
+        );
+    }
+    return (drop_prices, fuel_prices);
+}
+
+fn get_drops(
+    minion: &'static Value,
+    tier: usize,
+    fuel: &'static Value,
+    upgrades: [&'static Value; 2],
+) -> Vec<(&'static str, f64)> {
+    let speed = fuel["speed"].as_f64().unwrap()
+        + upgrades[0]["speed"].as_f64().unwrap()
+        + upgrades[1]["speed"].as_f64().unwrap();
+    let action_time = minion["action_times"][tier - 1].as_f64().unwrap() / (1.0 + speed);
+    let harvests = 3600.0 / (action_time * 2.0); // every other action places a block or spawns a mob
+    let multiplier = fuel["multiplier"].as_f64().unwrap();
+    let mut drops = Vec::new();
+    let mut total_items = 0.0;
+    for (item_id, amount) in minion["drops"].as_object().unwrap() {
+        let per_hour = harvests * amount.as_f64().unwrap() * multiplier;
+        drops.push((item_id.as_str(), per_hour));
+        total_items += per_hour;
+    }
+    let diamond_ratio =
+        upgrades[0]["diamonds"].as_f64().unwrap() + upgrades[1]["diamonds"].as_f64().unwrap();
+    if diamond_ratio > 0.0 {
+        let diamonds = total_items * diamond_ratio;
+        match drops.iter_mut().find(|(item_id, _)| item_id.eq(&"DIAMOND")) {
+            Some(entry) => entry.1 += diamonds,
+            None => drops.push(("DIAMOND", diamonds)),
+        }
+    }
+    return drops;
+}
+
+fn calculate_profit(
+    prices: &(HashMap<&'static str, f64>, HashMap<&'static str, f64>),
+    minion: &'static Value,
+    tier: usize,
+    fuel: &'static Value,
+    upgrades: [&'static Value; 2],
+) -> Profit {
+    let (drop_prices, fuel_prices) = prices;
+    let coins: f64 = get_drops(minion, tier, fuel, upgrades)
+        .iter()
+        .map(|(item_id, per_hour)| per_hour * drop_prices.get(item_id).unwrap_or(&0.0))
+        .sum();
+    let fuel_id = fuel["id"].as_str().unwrap();
+    let fuel_cost = match fuel["duration"].as_f64() {
+        Some(duration) => fuel_prices.get(fuel_id).unwrap_or(&0.0) / duration,
+        None => 0.0, // permanent fuels are a one-time purchase
+    };
+    return Profit {
+        minion,
+        tier,
+        fuel,
+        upgrades,
+        fuel_cost,
+        coins_per_hour: coins - fuel_cost,
+    };
+}
+
+impl Profit {
+    fn to_json(&self) -> Value {
+        let mut drops = json!({});
+        for (item_id, per_hour) in get_drops(self.minion, self.tier, self.fuel, self.upgrades) {
+            drops[item_id] = json!(per_hour);
+        }
+        return json!({
+            "minion": self.minion["id"],
+            "name": self.minion["name"],
+            "tier": self.tier,
+            "fuel": self.fuel["id"],
+            "upgrades": [self.upgrades[0]["id"], self.upgrades[1]["id"]],
+            "drops_per_hour": drops,
+            "fuel_cost_per_hour": self.fuel_cost,
+            "coins_per_hour": self.coins_per_hour,
+            "coins_per_day": self.coins_per_hour * 24.0
+        });
+    }
+}
+
+// None if an unknown upgrade is requested, rather than quietly ranking it as no upgrade
+fn get_upgrade_pairs(query: &HashMap<String, String>) -> Option<Vec<[&'static Value; 2]>> {
+    let upgrades = MINIONS["upgrades"].as_array().unwrap();
+    let mut pairs = Vec::new();
+    if let Some(filter) = query.get("upgrades") {
+        let none = &upgrades[0];
+        let mut pair = [none, none];
+        for (slot, id) in filter.split(',').take(2).enumerate() {
+            pair[slot] = upgrades
+                .iter()
+                .find(|upgrade| upgrade["id"].as_str().unwrap().eq(&id.to_uppercase()))?;
+        }
+        pairs.push(pair);
+    } else {
+        for first in 0..upgrades.len() {
+            for second in first..upgrades.len() {
+                pairs.push([&upgrades[first], &upgrades[second]]);
+            }
+        }
+    }
+    return Some(pairs);
+}
+
+pub async fn get_profits_json(query: &HashMap<String, String>) -> Option<BoxBody> {
+    let sell = query
+        .get("sell")
+        .map_or("bazaar".to_string(), |sell| sell.to_lowercase());
+    if !sell.eq("bazaar") && !sell.eq("npc") {
+        return None;
+    }
+    let minion_filter = query.get("minion").map(|id| id.to_uppercase());
+    let tier_filter = query
+        .get("tier")
+        .and_then(|tier| tier.parse::<usize>().ok());
+    let fuel_filter = query.get("fuel").map(|id| id.to_uppercase());
+    let limit = query
+        .get("limit")
+        .and_then(|limit| limit.parse::<usize>().ok())
+        .unwrap_or(100)
+        .clamp(1, MAX_LIMIT);
+    let upgrade_pairs = get_upgrade_pairs(query)?;
+    let prices = get_prices(&sell).await;
+    let mut results = Vec::new();
+    for minion in MINIONS["minions"].as_array().unwrap() {
+        if minion_filter
+            .as_ref()
+            .is_some_and(|id| !minion["id"].as_str().unwrap().eq(id))
+        {
+            continue;
+        }
+        let max_tier = minion["action_times"].as_array().unwrap().len();
+        for tier in 1..=max_tier {
+            if tier_filter.is_some_and(|filter| filter != tier) {
+                continue;
+            }
+            for fuel in MINIONS["fuels"].as_array().unwrap() {
+                if fuel_filter
+                    .as_ref()
+                    .is_some_and(|id| !fuel["id"].as_str().unwrap().eq(id))
+                {
+                    continue;
+                }
+                for upgrades in upgrade_pairs.iter() {
+                    results.push(calculate_profit(&prices, minion, tier, fuel, *upgrades));
+                }
+            }
+        }
+    }
+    results.sort_by(|a, b| b.coins_per_hour.total_cmp(&a.coins_per_hour));
+    results.truncate(limit);
+    let json = json!({
+        "sell": sell,
+        "minions": results.iter().map(|profit| profit.to_json()).collect::<Vec<Value>>()
+    });
+    return Some(BoxBody::new(json.to_string()));
+}
@@ -0,0 +1,68 @@
+use std::sync::LazyLock;
+
+use serde_json::{Value, json};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{cache, util};
+
+static NEWS: LazyLock<Mutex<Option<Vec<Value>>>> = LazyLock::new(|| Mutex::new(None));
+
+pub async fn get() -> MutexGuard<'static, Option<Vec<Value>>> {
+    return NEWS.lock().await;
+}
+
+fn get_entry(item: &Value) -> Value {
+    return json!({
+        "title": item["title"].as_str().unwrap_or(""),
+        "text": item["text"].as_str().unwrap_or(""),
+        "link": item["link"].as_str().unwrap_or(""),
+        "material": item["item"]["material"].as_str()
+    });
+}
+
+fn get_embed(entry: &Value) -> Value {
+    return json!({
+        "embeds": [
+            {
+                "title": entry["title"],
+                "description": format!("{}\n\n[**Click here to read**]({})", entry["text"].as_str().unwrap(), entry["link"].as_str().unwrap()),
+                "color": 0x5ca0bf
+            }
+        ]
+    });
+}
+
+pub async fn get_news_json() -> Vec<u8> {
+    let json = json!({
+        "news": get().await.to_owned().unwrap_or_default()
+    });
+    return json.to_string().into_bytes();
+}
+
+pub async fn cache_news() {
+    cache::store("news", cache::JSON_MIME, get_news_json().await).await;
+}
+
+pub async fn refresh_news() {
+    let req = util::make_request("v2/skyblock/news").await;
+    if req.is_err() {
+        println!("Panicked while refreshing news data:\n{}", req.unwrap_err());
+    } else {
+        if let Some(json) = util::parse_json(req.unwrap()) {
+            let entries: Vec<Value> = json["items"]
+                .as_array()
+                .map_or(Vec::new(), |items| items.iter().map(get_entry).collect());
+            let mut news = get().await;
+            // entries are told apart by their link
+            let announced = util::get_new_entries(news.as_ref(), &entries, |known, entry| {
+                return known["link"] == entry["link"];
+            });
+            *news = Some(entries);
+            drop(news);
+            cache_news().await;
+            for entry in announced.iter() {
+                util::send_webhooks("NF_API_NEWS_WEBHOOKS", &get_embed(entry)).await;
+            }
+        }
+    }
+}
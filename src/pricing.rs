@@ -1,11 +1,11 @@
 use std::collections::HashMap;
 use std::sync::LazyLock;
 
-use actix_web::body::BoxBody;
+use crab_nbt::NbtCompound;
 use serde_json::{Value, json};
 use tokio::sync::{Mutex, MutexGuard};
 
-use crate::util;
+use crate::{anomalies, auctions, cache, dictionary, sniper, util};
 
 static PRICING: LazyLock<Mutex<HashMap<String, Value>>> =
     LazyLock::new(|| Mutex::new(HashMap::new()));
@@ -21,18 +21,234 @@ pub fn get_pricing(map: &MutexGuard<'static, HashMap<String, Value>>, pricing_ty
     return json!({});
 }
 
+pub fn get_item_pricing(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    pricing_type: &str,
+    item_id: &str,
+) -> Value {
+    if map.contains_key(pricing_type) {
+        return map.get(pricing_type).unwrap()[item_id].to_owned();
+    }
+    return Value::Null;
+}
+
+pub fn get_bazaar_price(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    item_id: &str,
+    side: &str,
+) -> Option<f64> {
+    return get_item_pricing(map, "bazaar", item_id)[side]
+        .as_f64()
+        .filter(|price| *price > 0.0);
+}
+
+pub fn get_npc_price(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    item_id: &str,
+) -> Option<f64> {
+    return get_item_pricing(map, "npc", item_id)["coin"].as_f64();
+}
+
+pub fn get_auction_price(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    item_id: &str,
+) -> Option<f64> {
+    return get_item_pricing(map, "auction", item_id).as_f64();
+}
+
+pub fn get_buy_price(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    item_id: &str,
+) -> Option<f64> {
+    return get_bazaar_price(map, item_id, "buy").or(get_auction_price(map, item_id));
+}
+
+pub fn get_sell_price(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    item_id: &str,
+) -> Option<f64> {
+    return get_bazaar_price(map, item_id, "sell")
+        .or(get_auction_price(map, item_id))
+        .or(get_npc_price(map, item_id));
+}
+
 pub async fn update_pricing(pricing_type: &str, json: Value) {
     get().await.insert(pricing_type.to_owned(), json);
+    cache_pricing().await;
+}
+
+pub async fn cache_pricing() {
+    cache::store("pricing", cache::JSON_MIME, get_pricing_json(None).await).await;
+    cache::store(
+        "pricing-msgpack",
+        cache::MSGPACK_MIME,
+        get_pricing_msgpack(None).await,
+    )
+    .await;
+}
+
+pub struct PricingFilter {
+    sources: Option<Vec<String>>,
+    items: Option<Vec<String>>,
+    fields: Option<Vec<String>>,
+}
+
+impl PricingFilter {
+    pub fn from_query(query: &HashMap<String, String>) -> Option<Self> {
+        let get_list = |name: &str| -> Option<Vec<String>> {
+            let list = query
+                .get(name)?
+                .split(',')
+                .filter(|entry| !entry.is_empty());
+            return Some(list.map(|entry| entry.to_owned()).collect());
+        };
+        let filter = PricingFilter {
+            sources: get_list("sources"),
+            items: get_list("items"),
+            fields: get_list("fields"),
+        };
+        if filter.sources.is_none() && filter.items.is_none() && filter.fields.is_none() {
+            return None; // unfiltered requests are served from the cached payload
+        }
+        return Some(filter);
+    }
+
+    fn has_source(&self, source: &str) -> bool {
+        return self
+            .sources
+            .as_ref()
+            .is_none_or(|sources| sources.iter().any(|entry| entry.eq(source)));
+    }
+
+    // entries ending with * match every item ID starting with the rest of the entry
+    fn has_item(&self, item_id: &str) -> bool {
+        return self.items.as_ref().is_none_or(|items| {
+            items.iter().any(|entry| match entry.strip_suffix('*') {
+                Some(prefix) => item_id.starts_with(&prefix.to_uppercase()),
+                None => item_id.eq(&entry.to_uppercase()),
+            })
+        });
+    }
+
+    fn has_field(&self, field: &str) -> bool {
+        return self
+            .fields
+            .as_ref()
+            .is_none_or(|fields| fields.iter().any(|entry| entry.eq(field)));
+    }
+}
+
+const PRICING_SOURCES: [&str; 3] = ["auction", "bazaar", "npc"];
+
+fn get_source_fields(source: &str) -> &'static [&'static str] {
+    return match source {
+        "bazaar" => &["buy", "sell", "flags"],
+        "npc" => &["coin", "mote"],
+        _ => &[],
+    };
+}
+
+fn get_fields<'a>(source: &str, filter: Option<&PricingFilter>) -> Vec<&'a str> {
+    return get_source_fields(source)
+        .iter()
+        .filter(|field| filter.is_none_or(|filter| filter.has_field(field)))
+        .copied()
+        .collect();
+}
+
+// sources without any of the requested fields are left out entirely instead of being sent empty
+fn is_missing_fields(fields: &[&str], filter: Option<&PricingFilter>) -> bool {
+    return filter.is_some_and(|filter| filter.fields.is_some()) && fields.is_empty();
+}
+
+fn get_included<'a>(
+    map: &'a MutexGuard<'static, HashMap<String, Value>>,
+    source: &str,
+    filter: Option<&PricingFilter>,
+) -> Vec<(&'a String, &'a Value)> {
+    let mut entries = Vec::new();
+    if let Some(section) = map.get(source).and_then(|section| section.as_object()) {
+        for (id, price) in section.iter() {
+            if filter.is_none_or(|filter| filter.has_item(id)) {
+                entries.push((id, price));
+            }
+        }
+    }
+    return entries;
 }
 
-pub async fn get_pricing_json() -> BoxBody {
+pub async fn get_pricing_json(filter: Option<&PricingFilter>) -> Vec<u8> {
     let map = get().await;
-    let json = json!({
-        "auction": get_pricing(&map, "auction"),
-        "bazaar": get_pricing(&map, "bazaar"),
-        "npc": get_pricing(&map, "npc")
+    let mut json = json!({});
+    for source in PRICING_SOURCES {
+        if filter.is_some_and(|filter| !filter.has_source(source)) {
+            continue;
+        }
+        let fields = get_fields(source, filter);
+        if is_missing_fields(&fields, filter) {
+            continue;
+        }
+        let mut section = json!({});
+        for (id, price) in get_included(&map, source, filter) {
+            if !price.is_object() {
+                section[id] = price.to_owned();
+                continue;
+            }
+            let mut prices = json!({});
+            for field in fields.iter() {
+                if !price[field].is_null() {
+                    prices[field] = price[field].to_owned();
+                }
+            }
+            if !prices.as_object().unwrap().is_empty() {
+                section[id] = prices;
+            }
+        }
+        json[source] = section;
+    }
+    return json.to_string().into_bytes();
+}
+
+pub async fn get_pricing_msgpack(filter: Option<&PricingFilter>) -> Vec<u8> {
+    let map = get().await;
+    let mut dictionary = dictionary::get().await;
+    let mut payload = json!({});
+    for source in PRICING_SOURCES {
+        if filter.is_some_and(|filter| !filter.has_source(source)) {
+            continue;
+        }
+        let fields = get_fields(source, filter);
+        if is_missing_fields(&fields, filter) {
+            continue;
+        }
+        let mut ids = Vec::new();
+        let mut columns: Vec<Vec<Value>> = vec![Vec::new(); fields.len().max(1)];
+        for (id, price) in get_included(&map, source, filter) {
+            if price.is_object() && fields.iter().all(|field| price[field].is_null()) {
+                continue;
+            }
+            ids.push(json!(dictionary.get_index(id)));
+            if fields.is_empty() && !price.is_object() {
+                columns[0].push(price.to_owned());
+            }
+            for (column, field) in fields.iter().enumerate() {
+                columns[column].push(price[field].to_owned());
+            }
+        }
+        let mut section = json!({ "ids": ids });
+        if get_source_fields(source).is_empty() {
+            section["prices"] = json!(columns[0]);
+        }
+        for (column, field) in fields.iter().enumerate() {
+            section[field] = json!(columns[column]);
+        }
+        payload[source] = section;
+    }
+    payload["dictionary"] = json!({
+        "id": dictionary.id,
+        "size": dictionary.items.len()
     });
-    return BoxBody::new(json.to_string());
+    return rmp_serde::to_vec(&payload).unwrap();
 }
 
 pub async fn fetch_auctions_list() -> Vec<Value> {
@@ -61,58 +277,63 @@ pub async fn fetch_auctions_list() -> Vec<Value> {
     return auctions;
 }
 
+// returns None for malformed items, since user-supplied item bytes go through here as well
+pub fn get_item_id(extra: &NbtCompound) -> Option<String> {
+    let id = extra.get_string("id")?;
+    return Some(match id.as_str() {
+        "PET" => {
+            let pet_info: Value = serde_json::from_str(extra.get_string("petInfo")?).ok()?;
+            format!(
+                "{}_PET_{}",
+                pet_info["type"].as_str()?,
+                pet_info["tier"].as_str()?
+            )
+        }
+        "RUNE" | "UNIQUE_RUNE" => {
+            if let Some(rune_info) = extra.get_compound("runes") {
+                let tags = rune_info.child_tags.first()?;
+                format!("{}_{}_RUNE", tags.0, tags.1.extract_int()?)
+            } else {
+                "EMPTY_RUNE".to_owned()
+            }
+        }
+        "POTION" => {
+            if let Some(potion_id) = extra.get_string("potion") {
+                format!(
+                    "{}_{}_POTION",
+                    potion_id.to_uppercase(),
+                    extra.get_int("potion_level")?
+                )
+            } else {
+                "UNKNOWN_POTION".to_owned()
+            }
+        }
+        _ => id.to_owned(),
+    });
+}
+
 pub async fn refresh_auction_house() {
     let auctions = fetch_auctions_list().await;
     let mut auction_prices = json!({});
+    let mut decoded = Vec::new();
     for auction in &auctions {
-        if auction["bin"].as_bool().unwrap() {
-            let bytes = auction["item_bytes"].as_str().unwrap();
-            let nbt = util::parse_item_nbt(bytes).await;
-            let tag = nbt.get_compound("tag").unwrap();
-            if let Some(extra) = tag.get_compound("ExtraAttributes") {
-                let id = extra.get_string("id").unwrap();
-                let item_id = match id.as_str() {
-                    "PET" => {
-                        let pet_info = util::parse_json_str(extra.get_string("petInfo").unwrap());
-                        format!(
-                            "{}_PET_{}",
-                            pet_info["type"].as_str().unwrap(),
-                            pet_info["tier"].as_str().unwrap()
-                        )
-                    }
-                    "RUNE" | "UNIQUE_RUNE" => {
-                        if let Some(rune_info) = extra.get_compound("runes") {
-                            let tags = rune_info.child_tags.first().unwrap();
-                            format!("{}_{}_RUNE", tags.0, tags.1.extract_int().unwrap())
-                        } else {
-                            "EMPTY_RUNE".to_owned()
-                        }
-                    }
-                    "POTION" => {
-                        if let Some(potion_id) = extra.get_string("potion") {
-                            format!(
-                                "{}_{}_POTION",
-                                potion_id.to_uppercase(),
-                                extra.get_int("potion_level").unwrap()
-                            )
-                        } else {
-                            "UNKNOWN_POTION".to_owned()
-                        }
-                    }
-                    _ => id.to_owned(),
-                };
-                let price = auction["starting_bid"].as_f64().unwrap();
-                let current_price = auction_prices[&item_id].as_f64();
-                auction_prices[&item_id] = json!(if current_price.is_some() {
-                    current_price.unwrap().min(price)
+        if let Some(entry) = auctions::decode_auction(auction).await {
+            if entry.bin {
+                let current_price = auction_prices[&entry.item_id].as_f64();
+                auction_prices[&entry.item_id] = json!(if current_price.is_some() {
+                    current_price.unwrap().min(entry.price)
                 } else {
-                    price
+                    entry.price
                 });
             }
+            decoded.push(entry);
         }
     }
     if !auctions.is_empty() {
+        let previous_prices = get_pricing(&get().await, "auction");
+        sniper::find_snipes(&decoded, &previous_prices).await;
         update_pricing("auction", auction_prices).await;
+        auctions::update_auctions(decoded).await;
     }
 }
 
@@ -127,26 +348,19 @@ pub async fn refresh_bazaar() {
         let mut bazaar_prices = json!({});
         if let Some(json) = util::parse_json(req.unwrap()) {
             let products = json["products"].as_object().unwrap();
+            let mut snapshots = HashMap::new();
             for (id, data) in products.iter() {
-                let buy_summary = data["buy_summary"].as_array().unwrap();
-                let sell_summary = data["sell_summary"].as_array().unwrap();
-                if !bazaar_prices[id].is_object() {
-                    bazaar_prices[id] = json!({});
-                }
-                bazaar_prices[id]["buy"] = json!(if buy_summary.len() > 0 {
-                    buy_summary.first().unwrap()["pricePerUnit"]
-                        .as_f64()
-                        .unwrap()
-                } else {
-                    0.0
-                });
-                bazaar_prices[id]["sell"] = json!(if sell_summary.len() > 0 {
-                    sell_summary.first().unwrap()["pricePerUnit"]
-                        .as_f64()
-                        .unwrap()
-                } else {
-                    0.0
+                snapshots.insert(id.to_owned(), anomalies::Snapshot::from_product(data));
+            }
+            let flagged = anomalies::detect_anomalies(&snapshots).await;
+            for (id, snapshot) in snapshots.iter() {
+                bazaar_prices[id] = json!({
+                    "buy": snapshot.buy,
+                    "sell": snapshot.sell
                 });
+                if let Some(flags) = flagged.get(id) {
+                    bazaar_prices[id]["flags"] = json!(flags);
+                }
             }
             update_pricing("bazaar", bazaar_prices).await;
         }
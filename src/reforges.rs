@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+use tokio::sync::MutexGuard;
+
+use crate::{items, pricing, util};
+
+static REFORGES: LazyLock<Value> =
+    LazyLock::new(|| util::parse_json_str(include_str!("../data/reforges.json")));
+
+pub fn get_reforge(name: &str) -> Option<&'static Value> {
+    let reforge = &REFORGES["reforges"][name.to_lowercase()];
+    if reforge.is_object() {
+        return Some(reforge);
+    }
+    return None;
+}
+
+pub fn get_stone_value(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    name: &str,
+) -> Option<(String, f64)> {
+    let stone = get_reforge(name)?["stone"].as_str()?;
+    let value = pricing::get_sell_price(map, stone).unwrap_or(0.0);
+    return Some((stone.to_owned(), value));
+}
+
+pub async fn get_reforge_cost_json(query: &HashMap<String, String>) -> Option<BoxBody> {
+    let name = query.get("reforge")?.to_lowercase();
+    let reforge = get_reforge(&name)?;
+    let mut rarity = query.get("rarity").map(|rarity| rarity.to_uppercase());
+    let mut category = query
+        .get("category")
+        .map(|category| category.to_uppercase());
+    if let Some(item_id) = query.get("item") {
+        let data = items::get_data()
+            .await
+            .get(&item_id.to_uppercase())?
+            .to_owned();
+        rarity = rarity.or(data["tier"].as_str().map(|tier| tier.to_owned()));
+        category = category.or(data["category"].as_str().map(|c| c.to_owned()));
+    }
+    let rarity = rarity?;
+    let apply_cost = reforge["costs"][&rarity].as_f64()?;
+    let applicable = category.as_ref().is_none_or(|category| {
+        reforge["categories"]
+            .as_array()
+            .unwrap()
+            .contains(&json!(category))
+    });
+    let map = pricing::get().await;
+    let stone = reforge["stone"].as_str().unwrap();
+    let stone_price = pricing::get_buy_price(&map, stone);
+    let json = json!({
+        "reforge": name,
+        "rarity": rarity,
+        "category": category,
+        "applicable": applicable,
+        "stone": stone,
+        "stone_price": stone_price,
+        "apply_cost": apply_cost,
+        "cost": stone_price.unwrap_or(0.0) + apply_cost
+    });
+    return Some(BoxBody::new(json.to_string()));
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+use tokio::sync::{Mutex, MutexGuard};
+
+use crate::{cache, pricing};
+
+static COLLECTIONS: LazyLock<Mutex<Vec<Value>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+static SKILLS: LazyLock<Mutex<Vec<Value>>> = LazyLock::new(|| Mutex::new(Vec::new()));
+
+pub async fn get_collections() -> MutexGuard<'static, Vec<Value>> {
+    return COLLECTIONS.lock().await;
+}
+
+pub async fn get_skills() -> MutexGuard<'static, Vec<Value>> {
+    return SKILLS.lock().await;
+}
+
+pub async fn get_skills_json() -> Vec<u8> {
+    let json = json!({
+        "skills": *get_skills().await
+    });
+    return json.to_string().into_bytes();
+}
+
+pub async fn cache_skills() {
+    cache::store("skills", cache::JSON_MIME, get_skills_json().await).await;
+}
+
+// prices change between requests, so unlike the skill tables this is built on demand
+pub async fn get_collections_json(query: &HashMap<String, String>) -> BoxBody {
+    let category = query
+        .get("category")
+        .map(|category| category.to_uppercase());
+    let item = query.get("item").map(|item| item.to_uppercase());
+    let collections = get_collections().await;
+    let map = pricing::get().await;
+    let mut list = Vec::new();
+    for collection in collections.iter() {
+        if category
+            .as_ref()
+            .is_some_and(|category| collection["category"].ne(category))
+            || item.as_ref().is_some_and(|item| collection["id"].ne(item))
+        {
+            continue;
+        }
+        let id = collection["id"].as_str().unwrap();
+        let max_amount = collection["tiers"]
+            .as_array()
+            .and_then(|tiers| tiers.last())
+            .and_then(|tier| tier.as_u64())
+            .unwrap_or(0);
+        let buy_price = pricing::get_bazaar_price(&map, id, "buy");
+        let sell_price = pricing::get_bazaar_price(&map, id, "sell");
+        let mut json = collection.to_owned();
+        json["buy_price"] = json!(buy_price);
+        json["sell_price"] = json!(sell_price);
+        // the cost of buying every item needed for the final tier straight from the bazaar
+        json["coins_to_max"] = json!(
+            buy_price
+                .or(sell_price)
+                .map(|price| price * max_amount as f64)
+        );
+        list.push(json);
+    }
+    let json = json!({ "collections": list });
+    return BoxBody::new(json.to_string());
+}
+
+pub async fn refresh_collections(json: &Value) {
+    let mut list = Vec::new();
+    for (category, collection) in json["collections"].as_object().unwrap().iter() {
+        for (id, item) in collection["items"].as_object().unwrap().iter() {
+            let tiers: Vec<Value> = item["tiers"].as_array().map_or(Vec::new(), |tiers| {
+                tiers
+                    .iter()
+                    .map(|tier| tier["amountRequired"].to_owned())
+                    .collect()
+            });
+            list.push(json!({
+                "id": id,
+                "name": item["name"],
+                "category": category,
+                "max_tier": item["maxTiers"],
+                "tiers": tiers
+            }));
+        }
+    }
+    *get_collections().await = list;
+}
+
+pub async fn refresh_skills(json: &Value) {
+    let mut list = Vec::new();
+    for (id, skill) in json["skills"].as_object().unwrap().iter() {
+        let mut previous = 0.0;
+        let mut levels = Vec::new();
+        for level in skill["levels"].as_array().unwrap_or(&Vec::new()) {
+            let total = level["totalExpRequired"].as_f64().unwrap_or(0.0);
+            levels.push(json!({
+                "level": level["level"],
+                "xp": total - previous,
+                "total_xp": total
+            }));
+            previous = total;
+        }
+        list.push(json!({
+            "id": id,
+            "name": skill["name"],
+            "max_level": skill["maxLevel"],
+            "levels": levels
+        }));
+    }
+    *get_skills().await = list;
+    cache_skills().await;
+}
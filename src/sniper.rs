@@ -0,0 +1,68 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::LazyLock;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+use tokio::sync::{
+    Mutex, MutexGuard,
+    broadcast::{self, Sender},
+};
+
+use crate::{auctions::Auction, util};
+
+static SEEN_AUCTIONS: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+
+static SNIPE_EVENTS: LazyLock<Sender<Value>> = LazyLock::new(|| broadcast::channel(1024).0);
+
+const SNIPE_RATIO: f64 = 0.85; // listings must be at least 15% below the reference price
+
+pub async fn get() -> MutexGuard<'static, HashSet<String>> {
+    return SEEN_AUCTIONS.lock().await;
+}
+
+pub async fn find_snipes(auctions: &[Auction], previous_prices: &Value) {
+    let mut seen = get().await;
+    let first_refresh = seen.is_empty(); // every listing is "new" on startup, so nothing is sent
+    let mut item_prices: HashMap<&str, Vec<f64>> = HashMap::new();
+    let listings: Vec<&Auction> = auctions.iter().filter(|auction| auction.bin).collect();
+    for listing in &listings {
+        item_prices
+            .entry(listing.item_id.as_str())
+            .or_default()
+            .push(listing.price);
+    }
+    let mut medians = HashMap::new();
+    for (item_id, prices) in item_prices.iter_mut() {
+        medians.insert(*item_id, util::get_median(prices));
+    }
+    for listing in &listings {
+        if first_refresh || seen.contains(&listing.uuid) {
+            continue;
+        }
+        let median = medians.get(listing.item_id.as_str()).unwrap().to_owned();
+        let reference = previous_prices[&listing.item_id].as_f64().unwrap_or(median);
+        if listing.price <= reference * SNIPE_RATIO {
+            let _ = SNIPE_EVENTS.send(json!({
+                "uuid": listing.uuid,
+                "item_id": listing.item_id,
+                "price": listing.price,
+                "profit": reference - listing.price,
+                "lowest_bin": previous_prices[&listing.item_id],
+                "median": median
+            }));
+        }
+    }
+    seen.clear();
+    for listing in &listings {
+        seen.insert(listing.uuid.to_owned());
+    }
+}
+
+pub fn subscribe(min_profit: f64, items: HashSet<String>) -> BoxBody {
+    return util::event_stream(SNIPE_EVENTS.subscribe(), move |event| {
+        let item_id = event["item_id"].as_str().unwrap_or("");
+        return event["profit"].as_f64().unwrap_or(0.0) >= min_profit
+            && (items.is_empty() || items.contains(item_id));
+    });
+}
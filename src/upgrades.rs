@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+use tokio::sync::MutexGuard;
+
+use crate::{items, pricing};
+
+const MASTER_STARS: [&str; 5] = [
+    "FIRST_MASTER_STAR",
+    "SECOND_MASTER_STAR",
+    "THIRD_MASTER_STAR",
+    "FOURTH_MASTER_STAR",
+    "FIFTH_MASTER_STAR",
+];
+
+pub fn get_costs_price(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    costs: &Value,
+) -> (Vec<Value>, f64) {
+    let mut list = Vec::new();
+    let mut total = 0.0;
+    for cost in costs.as_array().unwrap_or(&Vec::new()) {
+        let (id, amount, price) = match cost["type"].as_str().unwrap_or("") {
+            "COINS" => (
+                "COINS".to_owned(),
+                cost["coins"].as_f64().unwrap_or(0.0),
+                Some(1.0),
+            ),
+            "ESSENCE" => {
+                let id = format!("ESSENCE_{}", cost["essence_type"].as_str().unwrap_or(""));
+                let price = pricing::get_buy_price(map, &id);
+                (id, cost["amount"].as_f64().unwrap_or(0.0), price)
+            }
+            _ => {
+                let id = cost["item_id"].as_str().unwrap_or("").to_owned();
+                let price = pricing::get_buy_price(map, &id);
+                (id, cost["amount"].as_f64().unwrap_or(1.0), price)
+            }
+        };
+        total += price.unwrap_or(0.0) * amount;
+        list.push(json!({
+            "id": id,
+            "amount": amount,
+            "price": price
+        }));
+    }
+    return (list, total);
+}
+
+pub fn get_max_stars(item_data: &Value) -> usize {
+    let stars = item_data["upgrade_costs"]
+        .as_array()
+        .map_or(0, |costs| costs.len());
+    if item_data["dungeon_item"].as_bool().unwrap_or(false) && stars == 5 {
+        return stars + MASTER_STARS.len();
+    }
+    return stars;
+}
+
+fn get_level_costs(item_data: &Value, level: usize) -> Value {
+    let upgrade_costs = item_data["upgrade_costs"].as_array();
+    let stars = upgrade_costs.map_or(0, |costs| costs.len());
+    if level <= stars {
+        return upgrade_costs.unwrap()[level - 1].to_owned();
+    }
+    // master stars are consumed as items instead of being listed in the upgrade costs
+    return json!([{
+        "type": "ITEM",
+        "item_id": MASTER_STARS[level - stars - 1],
+        "amount": 1
+    }]);
+}
+
+pub fn get_star_costs(
+    map: &MutexGuard<'static, HashMap<String, Value>>,
+    item_data: &Value,
+    from: usize,
+    to: usize,
+) -> (Vec<Value>, f64) {
+    let mut levels = Vec::new();
+    let mut total = 0.0;
+    for level in from.saturating_add(1)..=to.min(get_max_stars(item_data)) {
+        let (costs, cost) = get_costs_price(map, &get_level_costs(item_data, level));
+        total += cost;
+        levels.push(json!({
+            "level": level,
+            "costs": costs,
+            "cost": cost
+        }));
+    }
+    return (levels, total);
+}
+
+pub async fn get_upgrade_cost_json(query: &HashMap<String, String>) -> Option<BoxBody> {
+    let id = query.get("item")?.to_uppercase();
+    let item_data = items::get_data().await.get(&id)?.to_owned();
+    let max_stars = get_max_stars(&item_data);
+    let from = query
+        .get("from")
+        .and_then(|from| from.parse::<usize>().ok())
+        .unwrap_or(0);
+    let to = query
+        .get("to")
+        .and_then(|to| to.parse::<usize>().ok())
+        .unwrap_or(max_stars);
+    if from >= to || to > max_stars {
+        return None;
+    }
+    let map = pricing::get().await;
+    let (levels, total) = get_star_costs(&map, &item_data, from, to);
+    let json = json!({
+        "item": id,
+        "from": from,
+        "to": to,
+        "max_stars": max_stars,
+        "levels": levels,
+        "cost": total
+    });
+    return Some(BoxBody::new(json.to_string()));
+}
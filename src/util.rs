@@ -1,15 +1,26 @@
 use std::{
     env,
     io::Read,
-    time::{SystemTime, UNIX_EPOCH},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+use actix_web::{body::BoxBody, web::Bytes};
 use base64::{Engine, engine::general_purpose};
 use crab_nbt::NbtCompound;
 use dotenvy::dotenv;
 use flate2::bufread::GzDecoder;
 use serde_json::Value;
-use ureq::{Agent, Body, config::AutoHeaderValue};
+use tokio::sync::broadcast::Receiver;
+use tokio::task;
+use tokio::time::interval;
+use tokio_stream::{
+    StreamExt,
+    wrappers::{BroadcastStream, IntervalStream},
+};
+use ureq::{Agent, AsSendBody, Body, config::AutoHeaderValue};
+
+const KEEPALIVE_INTERVAL: u64 = 30000;
+const GZIP_LIMIT: u64 = 1048576; // item NBT is a few kilobytes at most, anything past this is rejected
 
 fn get_http_agent() -> Agent {
     return Agent::config_builder()
@@ -25,6 +36,38 @@ pub async fn make_request(url: &str) -> Result<ureq::http::Response<ureq::Body>,
         .call();
 }
 
+// reads the response on the blocking pool, so request handlers don't stall their worker thread
+pub async fn fetch_json(url: String) -> Result<Option<Value>, ureq::Error> {
+    return task::spawn_blocking(move || {
+        let req = get_http_agent()
+            .get(format!("https://api.hypixel.net/{}", url))
+            .header("API-Key", env::var("HYPIXEL_API_KEY").unwrap())
+            .call()?;
+        return Ok(parse_json(req));
+    })
+    .await
+    .unwrap();
+}
+
+pub async fn http_post(
+    url: String,
+    body: impl AsSendBody,
+) -> Result<ureq::http::Response<ureq::Body>, ureq::Error> {
+    Agent::new_with_defaults()
+        .post(url)
+        .header("Content-Type", "application/json")
+        .send(body)
+}
+
+// the variable holds a comma separated list, so the same message can be sent to several servers
+pub async fn send_webhooks(var: &str, message: &Value) {
+    if let Ok(webhooks) = env::var(var) {
+        for webhook in webhooks.split(',').filter(|webhook| !webhook.is_empty()) {
+            let _ = http_post(webhook.trim().to_string(), message.to_string()).await;
+        }
+    }
+}
+
 pub fn get_timestamp() -> u128 {
     return SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -32,6 +75,30 @@ pub fn get_timestamp() -> u128 {
         .as_millis();
 }
 
+pub fn get_median(prices: &mut [f64]) -> f64 {
+    prices.sort_by(|a, b| a.total_cmp(b));
+    let middle = prices.len() / 2;
+    if prices.len().is_multiple_of(2) {
+        return (prices[middle - 1] + prices[middle]) / 2.0;
+    }
+    return prices[middle];
+}
+
+// without a previous list every entry would look new, so nothing is returned on the first load
+pub fn get_new_entries(
+    previous: Option<&Vec<Value>>,
+    entries: &[Value],
+    same: impl Fn(&Value, &Value) -> bool,
+) -> Vec<Value> {
+    return previous.map_or(Vec::new(), |previous| {
+        entries
+            .iter()
+            .filter(|entry| !previous.iter().any(|known| same(known, entry)))
+            .cloned()
+            .collect()
+    });
+}
+
 pub fn load_env_file() {
     let _ = dotenv().unwrap();
 }
@@ -49,30 +116,40 @@ pub fn parse_json_str(json_str: &str) -> Value {
     return json;
 }
 
-pub fn decode_base64(b64: &str) -> Vec<u8> {
-    return general_purpose::STANDARD.decode(b64).unwrap();
+pub fn decode_base64(b64: &str) -> Option<Vec<u8>> {
+    return general_purpose::STANDARD.decode(b64).ok();
+}
+
+pub fn decode_gzip(bytes: Vec<u8>) -> Option<Vec<u8>> {
+    // reads one byte past the limit, so oversized output can be told apart from output that fits exactly
+    let mut decoder = GzDecoder::new(&bytes[..]).take(GZIP_LIMIT + 1);
+    let mut output = Vec::new();
+    decoder.read_to_end(&mut output).ok()?;
+    if output.len() as u64 > GZIP_LIMIT {
+        return None;
+    }
+    return Some(output);
 }
 
-pub fn decode_gzip(bytes: Vec<u8>) -> Vec<u8> {
-    let mut decoder = GzDecoder::new(&bytes[..]);
-    let output = &mut Vec::new();
-    let _ = decoder.read_to_end(output).unwrap();
-    return output.to_vec();
+pub async fn parse_item_nbt(gzip: &str) -> Option<NbtCompound> {
+    let decoded = decode_gzip(decode_base64(gzip)?)?;
+    let compound = NbtCompound::deserialize_content(&mut decoded.as_slice()).ok()?;
+    let parent_tag = &compound.child_tags.first()?.1;
+    let tag_list = parent_tag.extract_compound()?.get_list("i")?;
+    return Some(tag_list.first()?.extract_compound()?.to_owned());
 }
 
-pub async fn parse_item_nbt(gzip: &str) -> NbtCompound {
-    let decoded = decode_gzip(decode_base64(gzip));
-    let compound = NbtCompound::deserialize_content(&mut decoded.as_slice()).unwrap();
-    let parent_tag = &compound.child_tags.first().unwrap().1;
-    let tag_list = parent_tag
-        .extract_compound()
-        .unwrap()
-        .get_list("i")
-        .unwrap();
-    return tag_list
-        .first()
-        .unwrap()
-        .extract_compound()
-        .unwrap()
-        .to_owned();
+pub fn event_stream(
+    receiver: Receiver<Value>,
+    filter: impl Fn(&Value) -> bool + 'static,
+) -> BoxBody {
+    let events = BroadcastStream::new(receiver).filter_map(move |event| match event {
+        Ok(json) if filter(&json) => Some(Bytes::from(format!("data: {}\n\n", json))),
+        _ => None, // lagged receivers skip the missed events instead of closing the stream
+    });
+    // proxies such as Cloudflare close connections that stay idle for around 100 seconds
+    let keepalive = IntervalStream::new(interval(Duration::from_millis(KEEPALIVE_INTERVAL)))
+        .map(|_| Bytes::from_static(b": keepalive\n\n"));
+    let stream = events.merge(keepalive).map(Ok::<Bytes, std::io::Error>);
+    return BoxBody::new(actix_web::body::BodyStream::new(stream));
 }
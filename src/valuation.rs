@@ -0,0 +1,49 @@
+use actix_web::body::BoxBody;
+use serde_json::{Value, json};
+
+use crate::{gems, items, pricing, reforges, upgrades, util};
+
+pub async fn get_item_value_json(body: &Value) -> Option<BoxBody> {
+    let nbt = util::parse_item_nbt(body["item_bytes"].as_str()?).await?;
+    let extra = nbt.get_compound("tag")?.get_compound("ExtraAttributes")?;
+    let base_id = extra.get_string("id")?.to_owned();
+    let item_id = pricing::get_item_id(extra)?;
+    let stars = extra
+        .get_int("upgrade_level")
+        .or(extra.get_int("dungeon_item_level"))
+        .unwrap_or(0)
+        .max(0) as usize;
+    let item_data = items::get_data()
+        .await
+        .get(&base_id)
+        .map(|data| data.to_owned())
+        .unwrap_or(json!({}));
+    let map = pricing::get().await;
+    let base = pricing::get_sell_price(&map, &item_id).unwrap_or(0.0);
+    let (_levels, star_cost) = upgrades::get_star_costs(&map, &item_data, 0, stars);
+    let reforge = extra.get_string("modifier").map(|name| name.to_owned());
+    let (stone, stone_value) = reforge
+        .as_ref()
+        .and_then(|name| reforges::get_stone_value(&map, name))
+        .map_or((None, 0.0), |(stone, value)| (Some(stone), value));
+    let gems = gems::get_gems_value(&map, extra, &item_data);
+    let gems_value = gems["value"].as_f64().unwrap();
+    let json = json!({
+        "id": item_id,
+        "value": base + star_cost + stone_value + gems_value,
+        "components": {
+            "base": base,
+            "stars": {
+                "level": stars,
+                "cost": star_cost
+            },
+            "reforge": {
+                "name": reforge,
+                "stone": stone,
+                "value": stone_value
+            },
+            "gems": gems
+        }
+    });
+    return Some(BoxBody::new(json.to_string()));
+}